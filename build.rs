@@ -8,8 +8,8 @@ fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
     let out_dir = Path::new(&out_dir);
     println!("Writing tests to: {:?}", out_dir);
-    write_tag_tests(&out_dir);
-    write_database_tests(&out_dir);
+    write_tag_tests(out_dir);
+    write_database_tests(out_dir);
 }
 
 fn write_tag_tests(path: &Path) {
@@ -94,6 +94,7 @@ fn write_database_tests(path: &Path) {
         test_file,
         r#"// THIS FILE IS AUTOGENERATED - DO NOT EDIT!
 use triseratops::library::database;
+use std::io::Cursor;
 "#
     )
     .unwrap();
@@ -108,16 +109,46 @@ use triseratops::library::database;
         let db_name = directory.file_name().unwrap();
         let db_name = db_name.to_str().expect("Failed to get DB name");
 
+        let serato_dir = directory.join("_Serato_");
+        let test_name = format!("serato_database_{}", db_name);
         write_database_test(
             &mut test_file,
-            directory.join("database V2").as_path(),
-            db_name,
+            serato_dir.join("database V2").as_path(),
+            &test_name,
         );
+
+        let subcrates_dir = serato_dir.join("Subcrates");
+        if !subcrates_dir.is_dir() {
+            continue;
+        }
+
+        for entry in subcrates_dir.read_dir().unwrap() {
+            let filepath = entry.unwrap().path();
+            if filepath.extension().and_then(|ext| ext.to_str()) != Some("crate") {
+                continue;
+            }
+
+            let crate_name: String = filepath
+                .file_stem()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_lowercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            let test_name = format!("serato_crate_{}_{}", db_name, crate_name);
+            write_database_test(&mut test_file, filepath.as_path(), &test_name);
+        }
     }
 }
 
-fn write_database_test(test_file: &mut File, filepath: &Path, db_name: &str) {
-    let test_name = format!("serato_database_{}", db_name);
+fn write_database_test(test_file: &mut File, filepath: &Path, test_name: &str) {
     write!(
         test_file,
         include_str!("./tests/database_parse.rs.in"),
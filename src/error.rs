@@ -44,6 +44,19 @@ pub enum Error {
     #[error("No tag data available")]
     NoTagDataAvailable,
 
+    /// Thrown when a beatgrid violates the invariants that Serato relies on.
+    #[error("Invalid beatgrid: {reason}")]
+    InvalidBeatgrid { reason: &'static str },
+
+    /// Thrown when trying to read tags from a file with an unsupported file type.
+    #[error("Unsupported file type")]
+    UnsupportedFileType,
+
+    /// Represents errors when reading ID3 tags.
+    #[cfg(feature = "id3")]
+    #[error("Failed to read ID3 tag")]
+    ID3Error(#[from] id3::Error),
+
     /// Represents all other cases of `std::io::Error`.
     #[error(transparent)]
     IOError(#[from] std::io::Error),
@@ -39,9 +39,20 @@
 //! }
 //! ```
 //!
-//! **Note:** This library does *not* provide means to read the metadata from music files directly, so you need to
-//! use other libraries (e.g. [`id3`](https://lib.rs/crates/id3) to do that. You can check the
-//! `examples/` directory for some toy examples.
+//! Tags can also be read from (and written to) media files directly. Some file types require
+//! enabling a crate feature (e.g. `id3` for MP3, AIFF and WAV files), see the
+//! [`file`](tag::file) module for details:
+//!
+//! ```
+//! use triseratops::tag::TagContainer;
+//!
+//! fn read_and_print_cues(path: &str) {
+//!     let tags = TagContainer::read_from_path(path).expect("Failed to read tags!");
+//!     for cue in tags.cues() {
+//!         println!("{:?}", cue);
+//!     }
+//! }
+//! ```
 //!
 //! ## Serialization
 //!
@@ -70,7 +81,7 @@
 //! | `BeatGrid`   | **Yes** | **Yes** | **Yes** | No         | No             | Beatgrid Markers
 //! | `Markers_`   | **Yes** | *n/a*   | **Yes** | No         | No             | Hotcues, Saved Loops, etc.
 //! | `Markers2`   | **Yes** | **Yes** | **Yes** | **Yes**    | No             | Hotcues, Saved Loops, etc.
-//! | `Offsets_`   | No      | No      | No      | No         | No             | ?
+//! | `Offsets_`   | Partial | *n/a*   | *n/a*   | *n/a*      | No             | ?
 //! | `Overview`   | **Yes** | **Yes** | **Yes** | No         | No             | Overview Waveform data
 //! | `RelVol`     | *n/a*   | Partial | Partial | *n/a*      | No             | Relative Volume Adjument data (?)
 //! | `VideoAssoc` | *n/a*   | Partial | Partial | *n/a*      | No             | Video Association data (?)
@@ -79,12 +90,13 @@
 //!
 //! - Most Ogg tags are currently not supported. Their format is completely different from
 //!   the other tag types and need to be reverse-engineered first.
-//! - The `Serato Offsets_` tag haven't been reverse engineed yet, and no support has been
-//!   implemented.
+//! - The `Serato Offsets_` tag hasn't been reverse engineed yet, but preliminary support has been
+//!   added. For now, it just returns a tag version and a byte vector.
 //! - The `Serato RelVolAd` and the `Serato VidAssoc` tags haven't been reverse engineed yet, but
-//!   preliminary support has been added. For now, they just return a tag version and a byte vector.
+//!   preliminary support has been added. For now, they just return a tag version and a byte
+//!   vector.
 //! - AAC files (among others) do not store metadata in tags, and use XML files in the
-//! `_Serato_/Metadata` directory instead. No support has been added yet.
+//!   `_Serato_/Metadata` directory instead. No support has been added yet.
 //! - The cue colors stored in the metadata are *not* the same as displayed in Serato DJ Pro.
 //!   Instead, they uses the color palette from Serato DJ Into. Serato then maps them to a new
 //!   color palette. Support for converting between the two is currently missing.
@@ -98,11 +110,11 @@
 //!
 //! # Library
 //!
-//! Parsing the Serato library (e.g. the `database V2` file in the `_Serato_` directory) is also
-//! possible, but since this feature is still under development, the API is *not* stable yet and
-//! might change in the future.
+//! Parsing and writing the Serato library (e.g. the `database V2` file in the `_Serato_`
+//! directory) is also possible, but since this feature is still under development, the API is
+//! *not* stable yet and might change in the future.
 
 pub mod error;
 pub mod library;
 pub mod tag;
-pub(in crate) mod util;
+pub(crate) mod util;
@@ -2,6 +2,8 @@ extern crate nom;
 
 use crate::error::Error;
 use crate::util::Res;
+use std::io;
+use std::io::Cursor;
 use std::path::PathBuf;
 
 pub type Path = String;
@@ -13,8 +15,9 @@ const FIELD_PATH: u8 = b'p';
 const FIELD_TEXT: u8 = b't';
 const FIELD_U16: u8 = b's';
 const FIELD_U32: u8 = b'u';
+const FIELD_VERSION: u8 = b'v';
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Field {
     Unknown {
         field_type: u8,
@@ -121,8 +124,18 @@ fn parse_bool(input: &[u8]) -> Res<&[u8], bool> {
     Ok((input, value))
 }
 
-fn parse_field<'a, 'b>(input: &'a [u8], name: &'b [u8], field_type: u8) -> Res<&'a [u8], Field> {
+fn parse_field<'a>(input: &'a [u8], name: &[u8], field_type: u8) -> Res<&'a [u8], Field> {
     match field_type {
+        // Serato only writes `0x00` and `0x01`. Other values are kept as raw bytes, because they
+        // can't be written back from a `bool` without changing the data.
+        FIELD_BOOL if !matches!(input, [0x00] | [0x01]) => Ok((
+            &input[input.len()..],
+            Field::Unknown {
+                field_type,
+                name: name.to_vec(),
+                content: input.to_vec(),
+            },
+        )),
         FIELD_BOOL => {
             let (input, value) = nom::combinator::all_consuming(parse_bool)(input)?;
             let field = match name {
@@ -271,7 +284,160 @@ fn take_fields(input: &[u8]) -> Res<&[u8], Vec<Field>> {
     nom::multi::many1(take_field)(input)
 }
 
+/// Parse a list of [`Field`s](Field) from bytes (e.g. the content of a `database V2` file).
 pub fn parse(input: &[u8]) -> Result<Vec<Field>, Error> {
     let (_, fields) = nom::combinator::all_consuming(take_fields)(input)?;
     Ok(fields)
 }
+
+#[test]
+fn test_parse_bool_roundtrip() {
+    for data in [&b"bmis\0\0\0\x01\x01"[..], &b"bmis\0\0\0\x01\x02"[..]] {
+        let fields = parse(data).unwrap();
+        let mut output = Vec::new();
+        write(&mut output, &fields).unwrap();
+        assert_eq!(output, data);
+    }
+}
+
+fn write_field_header(
+    mut writer: impl io::Write,
+    field_type: u8,
+    name: &[u8],
+    length: usize,
+) -> Result<usize, Error> {
+    let mut bytes_written = writer.write(&[field_type])?;
+    bytes_written += writer.write(name)?;
+    bytes_written += writer.write(&(length as u32).to_be_bytes())?;
+    Ok(bytes_written)
+}
+
+fn write_raw_field(
+    mut writer: impl io::Write,
+    field_type: u8,
+    name: &[u8],
+    content: &[u8],
+) -> Result<usize, Error> {
+    let mut bytes_written = write_field_header(&mut writer, field_type, name, content.len())?;
+    bytes_written += writer.write(content)?;
+    Ok(bytes_written)
+}
+
+/// Returns the UTF-16 (big endian) encoded bytes of the given text.
+fn encode_u16_text(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(|c| c.to_be_bytes()).collect()
+}
+
+#[test]
+fn test_encode_u16_text() {
+    assert_eq!(encode_u16_text(""), Vec::<u8>::new());
+    assert_eq!(
+        encode_u16_text("mp3"),
+        vec![0x00, 0x6D, 0x00, 0x70, 0x00, 0x33]
+    );
+    assert_eq!(encode_u16_text("\u{1F3B5}"), vec![0xD8, 0x3C, 0xDF, 0xB5]);
+}
+
+fn write_text_field(writer: impl io::Write, name: &[u8], text: &str) -> Result<usize, Error> {
+    write_raw_field(writer, FIELD_TEXT, name, &encode_u16_text(text))
+}
+
+fn write_path_field(
+    writer: impl io::Write,
+    name: &[u8],
+    path: &std::path::Path,
+) -> Result<usize, Error> {
+    let path = path.to_string_lossy();
+    write_raw_field(writer, FIELD_PATH, name, &encode_u16_text(&path))
+}
+
+fn write_bool_field(writer: impl io::Write, name: &[u8], value: bool) -> Result<usize, Error> {
+    write_raw_field(writer, FIELD_BOOL, name, &[value as u8])
+}
+
+fn write_u16_field(writer: impl io::Write, name: &[u8], value: u16) -> Result<usize, Error> {
+    write_raw_field(writer, FIELD_U16, name, &value.to_be_bytes())
+}
+
+fn write_u32_field(writer: impl io::Write, name: &[u8], value: u32) -> Result<usize, Error> {
+    write_raw_field(writer, FIELD_U32, name, &value.to_be_bytes())
+}
+
+fn write_container_field(
+    writer: impl io::Write,
+    field_type: u8,
+    name: &[u8],
+    fields: &[Field],
+) -> Result<usize, Error> {
+    let mut buffer = Cursor::new(vec![]);
+    write(&mut buffer, fields)?;
+    write_raw_field(writer, field_type, name, buffer.get_ref())
+}
+
+/// Serialize a single [`Field`] to bytes.
+pub fn write_field(writer: impl io::Write, field: &Field) -> Result<usize, Error> {
+    match field {
+        Field::Unknown {
+            field_type,
+            name,
+            content,
+        } => write_raw_field(writer, *field_type, name, content),
+        Field::UnknownBoolean { name, value } => write_bool_field(writer, name, *value),
+        Field::UnknownContainerField { name, fields } => {
+            write_container_field(writer, FIELD_CONTAINER, name, fields)
+        }
+        Field::UnknownContainerRField { name, fields } => {
+            write_container_field(writer, FIELD_CONTAINER_R, name, fields)
+        }
+        Field::UnknownPathField { name, path } => write_path_field(writer, name, path),
+        Field::UnknownU16Field { name, value } => write_u16_field(writer, name, *value),
+        Field::UnknownU32Field { name, value } => write_u32_field(writer, name, *value),
+        Field::UnknownTextField { name, text } => write_text_field(writer, name, text),
+        // Library
+        Field::Album(text) => write_text_field(writer, b"alb", text),
+        Field::Artist(text) => write_text_field(writer, b"art", text),
+        Field::BPM(text) => write_text_field(writer, b"bpm", text),
+        Field::BeatgridLocked(value) => write_bool_field(writer, b"bgl", *value),
+        Field::Bitrate(text) => write_text_field(writer, b"bit", text),
+        Field::Comment(text) => write_text_field(writer, b"com", text),
+        Field::Composer(text) => write_text_field(writer, b"cmp", text),
+        Field::DateAdded(value) => write_u32_field(writer, b"add", *value),
+        Field::DateAddedStr(text) => write_text_field(writer, b"add", text),
+        Field::FilePath(path) => write_path_field(writer, b"fil", path),
+        Field::FileSize(text) => write_text_field(writer, b"siz", text),
+        Field::FileTime(value) => write_u32_field(writer, b"tme", *value),
+        Field::FileType(text) => write_text_field(writer, b"typ", text),
+        Field::Genre(text) => write_text_field(writer, b"gen", text),
+        Field::Grouping(text) => write_text_field(writer, b"grp", text),
+        Field::Key(text) => write_text_field(writer, b"key", text),
+        Field::Label(text) => write_text_field(writer, b"lbl", text),
+        Field::Length(text) => write_text_field(writer, b"len", text),
+        Field::Missing(value) => write_bool_field(writer, b"mis", *value),
+        Field::SampleRate(text) => write_text_field(writer, b"smp", text),
+        Field::SongTitle(text) => write_text_field(writer, b"sng", text),
+        Field::Track(fields) => write_container_field(writer, FIELD_CONTAINER, b"trk", fields),
+        // Special case: `vrsn` is a text field but begins with `v`
+        Field::Version(text) => {
+            write_raw_field(writer, FIELD_VERSION, b"rsn", &encode_u16_text(text))
+        }
+        Field::Year(text) => write_text_field(writer, b"tyr", text),
+        // Crates
+        Field::Sorting(fields) => write_container_field(writer, FIELD_CONTAINER, b"srt", fields),
+        Field::ReverseOrder(value) => write_bool_field(writer, b"rev", *value),
+        Field::ColumnTitle(fields) => {
+            write_container_field(writer, FIELD_CONTAINER, b"vct", fields)
+        }
+        Field::ColumnName(text) => write_text_field(writer, b"vcn", text),
+        Field::ColumnWidth(text) => write_text_field(writer, b"vcw", text),
+        Field::TrackPath(path) => write_path_field(writer, b"trk", path),
+    }
+}
+
+/// Serialize a list of [`Field`s](Field) to bytes (e.g. the content of a `database V2` file).
+pub fn write(mut writer: impl io::Write, fields: &[Field]) -> Result<usize, Error> {
+    let mut bytes_written = 0;
+    for field in fields {
+        bytes_written += write_field(&mut writer, field)?;
+    }
+    Ok(bytes_written)
+}
@@ -1,5 +1,6 @@
 //! Parsers for the Serato library database and crates
 pub mod database;
-pub(self) mod parser;
+mod parser;
+mod util;
 
-pub use parser::{Library, Track};
+pub use parser::{Crate, CrateColumn, CrateSorting, Library, SubcrateNode, Track};
@@ -12,15 +12,31 @@
 //!     }
 //! }
 //! ```
+//!
+//! Subcrates can also be created and edited:
+//!
+//! ```
+//! use std::path::{Path, PathBuf};
+//! use triseratops::library::Library;
+//!
+//! fn add_to_new_crate(music_dir: &PathBuf, track_path: &Path) {
+//!     let library = Library::read_from_path(music_dir).unwrap();
+//!     library.create_subcrate("New Crate").unwrap();
+//!     library.add_subcrate_track("New Crate", track_path).unwrap();
+//! }
+//! ```
 
 use super::database;
+use super::util;
 use crate::error::Error;
+use crate::tag::file::replace_file;
 use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io;
 use std::io::{BufReader, Read};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 /// File name of the main database file
 const DATABASE_FILENAME: &str = "database V2";
@@ -30,6 +46,22 @@ const CRATE_EXTENSION: &str = "crate";
 const SERATO_DIR: &str = "_Serato_";
 /// Name of the directory containing subcrates inside the Serato directory
 const SUBCRATE_DIR: &str = "Subcrates";
+/// Separator between parent and child names in nested subcrate names
+const SUBCRATE_SEPARATOR: &str = "%%";
+/// Version string written to newly created crate files
+const CRATE_VERSION: &str = "1.0/Serato ScratchLive Crate";
+/// Column that newly created crates are sorted by
+const CRATE_DEFAULT_SORTING: &str = "#";
+/// Columns (and their widths) that are shown for newly created crates
+const CRATE_DEFAULT_COLUMNS: [(&str, u32); 7] = [
+    ("song", 450),
+    ("artist", 0),
+    ("bpm", 0),
+    ("key", 0),
+    ("album", 0),
+    ("length", 0),
+    ("comment", 0),
+];
 
 #[derive(Clone, Debug)]
 pub struct Track {
@@ -44,6 +76,15 @@ pub struct Track {
     pub grouping: Option<String>,
     pub label: Option<String>,
     pub key: Option<String>,
+    pub year: Option<String>,
+    pub bpm: Option<String>,
+    pub length: Option<String>,
+    pub bitrate: Option<String>,
+    pub sample_rate: Option<String>,
+    pub file_size: Option<String>,
+    pub file_time: Option<u32>,
+    pub date_added: Option<u32>,
+    pub date_added_str: Option<String>,
     pub missing: bool,
     pub beatgrid_locked: bool,
 }
@@ -63,6 +104,15 @@ impl Track {
             grouping: None,
             label: None,
             key: None,
+            year: None,
+            bpm: None,
+            length: None,
+            bitrate: None,
+            sample_rate: None,
+            file_size: None,
+            file_time: None,
+            date_added: None,
+            date_added_str: None,
             missing: false,
             beatgrid_locked: false,
         }
@@ -79,12 +129,18 @@ impl Track {
                 database::Field::FileType(file_type) => {
                     track.file_type = Some(file_type);
                 }
+                database::Field::SongTitle(title) => {
+                    track.title = Some(title);
+                }
                 database::Field::Album(album) => {
                     track.album = Some(album);
                 }
                 database::Field::Artist(artist) => {
                     track.artist = Some(artist);
                 }
+                database::Field::Genre(genre) => {
+                    track.genre = Some(genre);
+                }
                 database::Field::Comment(comment) => {
                     track.comment = Some(comment);
                 }
@@ -100,6 +156,33 @@ impl Track {
                 database::Field::Key(key) => {
                     track.key = Some(key);
                 }
+                database::Field::Year(year) => {
+                    track.year = Some(year);
+                }
+                database::Field::BPM(bpm) => {
+                    track.bpm = Some(bpm);
+                }
+                database::Field::Length(length) => {
+                    track.length = Some(length);
+                }
+                database::Field::Bitrate(bitrate) => {
+                    track.bitrate = Some(bitrate);
+                }
+                database::Field::SampleRate(sample_rate) => {
+                    track.sample_rate = Some(sample_rate);
+                }
+                database::Field::FileSize(file_size) => {
+                    track.file_size = Some(file_size);
+                }
+                database::Field::FileTime(file_time) => {
+                    track.file_time = Some(file_time);
+                }
+                database::Field::DateAdded(date_added) => {
+                    track.date_added = Some(date_added);
+                }
+                database::Field::DateAddedStr(date_added_str) => {
+                    track.date_added_str = Some(date_added_str);
+                }
                 database::Field::Missing(missing) => {
                     track.missing = missing;
                 }
@@ -112,6 +195,90 @@ impl Track {
 
         Ok(track)
     }
+
+    /// Returns the track's BPM value.
+    pub fn bpm_value(&self) -> Option<f64> {
+        self.bpm.as_deref().and_then(util::parse_bpm)
+    }
+
+    /// Sets the track's BPM value.
+    pub fn set_bpm_value(&mut self, bpm: f64) {
+        self.bpm = Some(util::format_bpm(bpm));
+    }
+
+    /// Returns the track's bitrate in kbps.
+    pub fn bitrate_kbps(&self) -> Option<f64> {
+        self.bitrate.as_deref().and_then(util::parse_bitrate)
+    }
+
+    /// Sets the track's bitrate in kbps.
+    pub fn set_bitrate_kbps(&mut self, kbps: f64) {
+        self.bitrate = Some(util::format_bitrate(kbps));
+    }
+
+    /// Returns the track's sample rate in Hz.
+    pub fn sample_rate_hz(&self) -> Option<u32> {
+        self.sample_rate
+            .as_deref()
+            .and_then(util::parse_sample_rate)
+    }
+
+    /// Sets the track's sample rate in Hz.
+    pub fn set_sample_rate_hz(&mut self, hz: u32) {
+        self.sample_rate = Some(util::format_sample_rate(hz));
+    }
+
+    /// Returns the track's file size in bytes.
+    ///
+    /// Since Serato only stores the rounded size (e.g. `14.6MB`), this is an approximation.
+    pub fn file_size_bytes(&self) -> Option<u64> {
+        self.file_size.as_deref().and_then(util::parse_file_size)
+    }
+
+    /// Sets the track's file size in bytes.
+    pub fn set_file_size_bytes(&mut self, bytes: u64) {
+        self.file_size = Some(util::format_file_size(bytes));
+    }
+
+    /// Returns the track's length.
+    pub fn duration(&self) -> Option<Duration> {
+        self.length.as_deref().and_then(util::parse_length)
+    }
+
+    /// Sets the track's length.
+    pub fn set_duration(&mut self, length: Duration) {
+        self.length = Some(util::format_length(length));
+    }
+
+    /// Returns the time when the track was added to the library.
+    pub fn date_added_time(&self) -> Option<SystemTime> {
+        self.date_added.map(util::timestamp_to_system_time)
+    }
+
+    /// Sets the time when the track was added to the library.
+    ///
+    /// This updates both the numeric and the text representation. Times that can't be
+    /// represented as 32-bit UNIX timestamp are ignored.
+    pub fn set_date_added_time(&mut self, time: SystemTime) {
+        if let Some(timestamp) = util::system_time_to_timestamp(time) {
+            self.date_added = Some(timestamp);
+            self.date_added_str = Some(timestamp.to_string());
+        }
+    }
+
+    /// Returns the modification time of the track's file.
+    pub fn file_time_modified(&self) -> Option<SystemTime> {
+        self.file_time.map(util::timestamp_to_system_time)
+    }
+
+    /// Sets the modification time of the track's file.
+    ///
+    /// Times that can't be represented as 32-bit UNIX timestamp are ignored.
+    pub fn set_file_time_modified(&mut self, time: SystemTime) {
+        if let Some(timestamp) = util::system_time_to_timestamp(time) {
+            self.file_time = Some(timestamp);
+        }
+    }
 }
 
 impl Default for Track {
@@ -120,6 +287,322 @@ impl Default for Track {
     }
 }
 
+/// A column that is shown in Serato's library view when the crate is selected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CrateColumn {
+    /// The column name (e.g. `song` or `bpm`).
+    pub name: String,
+    /// The column width in pixels.
+    pub width: u32,
+}
+
+impl CrateColumn {
+    /// Creates a column from the fields of an `ovct` field.
+    ///
+    /// Returns `None` if the fields can't be written back unchanged by [`CrateColumn::to_field`].
+    fn from_fields(fields: &[database::Field]) -> Option<Self> {
+        match fields {
+            [database::Field::ColumnName(name), database::Field::ColumnWidth(width_str)] => {
+                let width: u32 = width_str.parse().ok()?;
+                if width.to_string() != *width_str {
+                    return None;
+                }
+                Some(Self {
+                    name: name.clone(),
+                    width,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn to_field(&self) -> database::Field {
+        database::Field::ColumnTitle(vec![
+            database::Field::ColumnName(self.name.clone()),
+            database::Field::ColumnWidth(self.width.to_string()),
+        ])
+    }
+}
+
+/// The column (and direction) that a crate's tracks are sorted by.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CrateSorting {
+    /// The name of the column (e.g. `#` for the crate order).
+    pub column: String,
+    /// Indicates whether the sort order is reversed (i.e. descending).
+    pub reverse: bool,
+}
+
+impl CrateSorting {
+    /// Creates the sorting from the fields of an `osrt` field.
+    ///
+    /// Returns `None` if the fields can't be written back unchanged by [`CrateSorting::to_field`].
+    fn from_fields(fields: &[database::Field]) -> Option<Self> {
+        match fields {
+            [database::Field::ColumnName(column), database::Field::ReverseOrder(reverse)] => {
+                Some(Self {
+                    column: column.clone(),
+                    reverse: *reverse,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn to_field(&self) -> database::Field {
+        database::Field::Sorting(vec![
+            database::Field::ColumnName(self.column.clone()),
+            database::Field::ReverseOrder(self.reverse),
+        ])
+    }
+}
+
+/// The kind of a top-level field in a crate file.
+///
+/// Used to write the fields of a parsed crate back in their original order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CrateFieldKind {
+    Version,
+    Sorting,
+    Column,
+    Track,
+    Unknown,
+}
+
+/// A crate, as stored in the `.crate` files in the `_Serato_/Subcrates` directory.
+#[derive(Clone, Debug)]
+pub struct Crate {
+    /// The crate file version (e.g. `1.0/Serato ScratchLive Crate`).
+    pub version: String,
+    /// The sort column and direction.
+    pub sorting: Option<CrateSorting>,
+    /// The visible columns and their widths, in display order.
+    pub columns: Vec<CrateColumn>,
+    /// The paths of the tracks in this crate, in crate order.
+    ///
+    /// These paths may also refer to files that are not in the library database.
+    pub track_paths: Vec<PathBuf>,
+    /// Fields that are not understood by this library (preserved when writing).
+    ///
+    /// This includes sorting and column fields with unexpected content.
+    pub unknown_fields: Vec<database::Field>,
+    /// The order of the top-level fields in the parsed crate file.
+    field_order: Vec<CrateFieldKind>,
+    /// The original `otrk` fields of tracks that contain more than just the track path.
+    track_fields: Vec<(PathBuf, Vec<database::Field>)>,
+}
+
+impl Crate {
+    /// Creates a new, empty Crate object with the default sorting and columns of Serato DJ.
+    pub fn new() -> Self {
+        Self {
+            version: CRATE_VERSION.to_string(),
+            sorting: Some(CrateSorting {
+                column: CRATE_DEFAULT_SORTING.to_string(),
+                reverse: false,
+            }),
+            columns: CRATE_DEFAULT_COLUMNS
+                .iter()
+                .map(|(name, width)| CrateColumn {
+                    name: name.to_string(),
+                    width: *width,
+                })
+                .collect(),
+            track_paths: Vec::new(),
+            unknown_fields: Vec::new(),
+            field_order: Vec::new(),
+            track_fields: Vec::new(),
+        }
+    }
+
+    /// Creates a new Crate object from a list of database fields.
+    ///
+    /// Fields with unexpected content are stored in
+    /// [`unknown_fields`](Crate::unknown_fields), so that [`Crate::to_fields`] returns the same
+    /// fields in the same order.
+    pub fn from_fields(fields: Vec<database::Field>) -> Result<Self, Error> {
+        let mut krate = Self {
+            version: String::new(),
+            sorting: None,
+            columns: Vec::new(),
+            track_paths: Vec::new(),
+            unknown_fields: Vec::new(),
+            field_order: Vec::new(),
+            track_fields: Vec::new(),
+        };
+        for field in fields {
+            let kind = match field {
+                database::Field::Version(version)
+                    if !krate.field_order.contains(&CrateFieldKind::Version) =>
+                {
+                    krate.version = version;
+                    CrateFieldKind::Version
+                }
+                database::Field::Sorting(ref sorting_fields) if krate.sorting.is_none() => {
+                    match CrateSorting::from_fields(sorting_fields) {
+                        Some(sorting) => {
+                            krate.sorting = Some(sorting);
+                            CrateFieldKind::Sorting
+                        }
+                        None => krate.push_unknown_field(field),
+                    }
+                }
+                database::Field::ColumnTitle(ref column_fields) => {
+                    match CrateColumn::from_fields(column_fields) {
+                        Some(column) => {
+                            krate.columns.push(column);
+                            CrateFieldKind::Column
+                        }
+                        None => krate.push_unknown_field(field),
+                    }
+                }
+                database::Field::Track(track_fields) => match track_fields.as_slice() {
+                    [database::Field::TrackPath(path)] => {
+                        krate.track_paths.push(path.to_owned());
+                        CrateFieldKind::Track
+                    }
+                    _ => {
+                        let path = track_fields.iter().find_map(|field| match field {
+                            database::Field::TrackPath(path) => Some(path.to_owned()),
+                            _ => None,
+                        });
+                        match path {
+                            Some(path) => {
+                                krate.track_paths.push(path.clone());
+                                krate.track_fields.push((path, track_fields));
+                                CrateFieldKind::Track
+                            }
+                            None => krate.push_unknown_field(database::Field::Track(track_fields)),
+                        }
+                    }
+                },
+                field => krate.push_unknown_field(field),
+            };
+            krate.field_order.push(kind);
+        }
+
+        Ok(krate)
+    }
+
+    fn push_unknown_field(&mut self, field: database::Field) -> CrateFieldKind {
+        self.unknown_fields.push(field);
+        CrateFieldKind::Unknown
+    }
+
+    fn track_field(&self, path: &Path) -> database::Field {
+        match self.track_fields.iter().find(|(p, _)| p == path) {
+            Some((_, fields)) => database::Field::Track(fields.clone()),
+            None => database::Field::Track(vec![database::Field::TrackPath(path.to_owned())]),
+        }
+    }
+
+    /// Returns the list of database fields that represent this crate.
+    ///
+    /// If the crate was parsed from a file, the fields are returned in their original order.
+    /// Sorting and columns that were added afterwards are inserted after the last column, other
+    /// new fields are appended.
+    pub fn to_fields(&self) -> Vec<database::Field> {
+        let mut version = Some(database::Field::Version(self.version.clone()));
+        let mut sorting = self.sorting.as_ref();
+        let mut columns = self.columns.iter();
+        let mut track_paths = self.track_paths.iter();
+        let mut unknown_fields = self.unknown_fields.iter();
+
+        let mut fields = Vec::new();
+        let mut header_end = 0;
+        for kind in &self.field_order {
+            let field = match kind {
+                CrateFieldKind::Version => version.take(),
+                CrateFieldKind::Sorting => sorting.take().map(CrateSorting::to_field),
+                CrateFieldKind::Column => columns.next().map(CrateColumn::to_field),
+                CrateFieldKind::Track => track_paths.next().map(|path| self.track_field(path)),
+                CrateFieldKind::Unknown => unknown_fields.next().cloned(),
+            };
+            if let Some(field) = field {
+                fields.push(field);
+                if !matches!(kind, CrateFieldKind::Track | CrateFieldKind::Unknown) {
+                    header_end = fields.len();
+                }
+            }
+        }
+
+        let mut header: Vec<database::Field> = version.into_iter().collect();
+        header.extend(sorting.map(CrateSorting::to_field));
+        header.extend(columns.map(CrateColumn::to_field));
+        fields.splice(header_end..header_end, header);
+        fields.extend(unknown_fields.cloned());
+        fields.extend(track_paths.map(|path| self.track_field(path)));
+        fields
+    }
+}
+
+impl Default for Crate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for Crate {
+    /// Compares the public crate data, ignoring the bookkeeping that is only used to preserve the
+    /// original field layout when writing.
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version
+            && self.sorting == other.sorting
+            && self.columns == other.columns
+            && self.track_paths == other.track_paths
+            && self.unknown_fields == other.unknown_fields
+    }
+}
+
+/// A subcrate in the nested subcrate hierarchy.
+///
+/// Serato encodes the nesting of subcrates in their file names, e.g. the subcrate `French House`
+/// inside the `House` subcrate is stored as `House%%French House.crate`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubcrateNode {
+    /// The full subcrate name (e.g. `House%%French House`) that is used to access the subcrate.
+    pub full_name: String,
+    /// The child subcrates, sorted by name.
+    pub children: Vec<SubcrateNode>,
+}
+
+impl SubcrateNode {
+    /// Returns the displayed name of this subcrate (e.g. `French House`).
+    pub fn name(&self) -> &str {
+        match self.full_name.rfind(SUBCRATE_SEPARATOR) {
+            Some(index) => &self.full_name[index + SUBCRATE_SEPARATOR.len()..],
+            None => &self.full_name,
+        }
+    }
+
+    /// Returns the full name of the parent subcrate, or `None` if this is a top-level subcrate.
+    pub fn parent_name(&self) -> Option<&str> {
+        self.full_name
+            .rfind(SUBCRATE_SEPARATOR)
+            .map(|index| &self.full_name[..index])
+    }
+
+    /// Returns the names of all subcrates from the top level down to this one.
+    pub fn path(&self) -> Vec<&str> {
+        self.full_name.split(SUBCRATE_SEPARATOR).collect()
+    }
+
+    /// Returns the nesting depth of this subcrate (`0` for top-level subcrates).
+    pub fn depth(&self) -> usize {
+        self.full_name.matches(SUBCRATE_SEPARATOR).count()
+    }
+
+    /// Returns an iterator over this subcrate and all of its descendants (depth-first).
+    pub fn iter(&self) -> impl Iterator<Item = &SubcrateNode> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+}
+
 /// DAO that reads Serato libraries from the file system.
 pub struct Library {
     path: PathBuf,
@@ -183,42 +666,294 @@ impl Library {
             .filter_map(|x| crate_name_from_path(&x).ok())
     }
 
-    /// Get a list of tracks from the subcrate with the given name.
-    pub fn subcrate(&self, name: &str) -> Result<impl Iterator<Item = &Track>, Error> {
+    /// Get the nested subcrate hierarchy.
+    ///
+    /// Returns the top-level subcrates, sorted by name. Parent subcrates that don't have a crate
+    /// file of their own are included, too.
+    pub fn subcrate_tree(&self) -> Vec<SubcrateNode> {
+        let mut roots: Vec<SubcrateNode> = Vec::new();
+        for name in self.subcrates() {
+            let mut nodes = &mut roots;
+            let mut full_name = String::new();
+            for component in name.split(SUBCRATE_SEPARATOR) {
+                if !full_name.is_empty() {
+                    full_name.push_str(SUBCRATE_SEPARATOR);
+                }
+                full_name.push_str(component);
+
+                let index = match nodes.iter().position(|node| node.full_name == full_name) {
+                    Some(index) => index,
+                    None => {
+                        nodes.push(SubcrateNode {
+                            full_name: full_name.clone(),
+                            children: Vec::new(),
+                        });
+                        nodes.len() - 1
+                    }
+                };
+                nodes = &mut nodes[index].children;
+            }
+        }
+
+        sort_subcrate_nodes(&mut roots);
+        roots
+    }
+
+    /// Create a new, empty subcrate with the given name inside the `parent` subcrate.
+    ///
+    /// If `parent` is `None`, a top-level subcrate is created. Returns the full name of the new
+    /// subcrate.
+    pub fn create_nested_subcrate(
+        &self,
+        parent: Option<&str>,
+        name: &str,
+    ) -> Result<String, Error> {
+        let full_name = nested_subcrate_name(parent, name)?;
+        self.create_subcrate(&full_name)?;
+        Ok(full_name)
+    }
+
+    /// Move the subcrate with the given full name (and all of its children) into the `parent`
+    /// subcrate.
+    ///
+    /// If `parent` is `None`, the subcrate becomes a top-level subcrate. Returns the new full name
+    /// of the subcrate.
+    pub fn move_subcrate(&self, name: &str, parent: Option<&str>) -> Result<String, Error> {
+        let child_name = match name.rfind(SUBCRATE_SEPARATOR) {
+            Some(index) => &name[index + SUBCRATE_SEPARATOR.len()..],
+            None => name,
+        };
+        let new_name = nested_subcrate_name(parent, child_name)?;
+        let descendant_prefix = format!("{}{}", name, SUBCRATE_SEPARATOR);
+        if let Some(parent) = parent {
+            if parent == name || parent.starts_with(&descendant_prefix) {
+                return Err(Error::IOError(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "cannot move subcrate into itself",
+                )));
+            }
+        }
+
+        if !self.subcrate_path(name)?.exists() {
+            return Err(Error::IOError(io::Error::new(
+                io::ErrorKind::NotFound,
+                "subcrate does not exist",
+            )));
+        }
+
+        if new_name == name {
+            return Ok(new_name);
+        }
+
+        let renames: Vec<(String, String)> = self
+            .subcrates()
+            .filter_map(|old_name| {
+                if old_name == name {
+                    Some((old_name, new_name.clone()))
+                } else {
+                    old_name
+                        .strip_prefix(&descendant_prefix)
+                        .map(|suffix| format!("{}{}{}", new_name, SUBCRATE_SEPARATOR, suffix))
+                        .map(|new_child_name| (old_name.clone(), new_child_name))
+                }
+            })
+            .collect();
+        for (_, new_name) in &renames {
+            if self.subcrate_path(new_name)?.exists() {
+                return Err(Error::IOError(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "subcrate already exists",
+                )));
+            }
+        }
+        for (old_name, new_name) in renames {
+            fs::rename(
+                self.subcrate_path(&old_name)?,
+                self.subcrate_path(&new_name)?,
+            )?;
+        }
+
+        Ok(new_name)
+    }
+
+    /// Get the path of the crate file for the subcrate with the given name.
+    ///
+    /// Fails for names that would point outside of the `Subcrates` directory.
+    fn subcrate_path(&self, name: &str) -> Result<PathBuf, Error> {
+        if name.is_empty()
+            || name.contains(['/', '\\', '\0'])
+            || Path::new(name)
+                .components()
+                .any(|component| component == Component::ParentDir)
+            || Path::new(name).is_absolute()
+        {
+            return Err(Error::IOError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid subcrate name",
+            )));
+        }
+
         let filename = format!("{}.{}", name, CRATE_EXTENSION);
-        let crate_path = self.serato_path().join(SUBCRATE_DIR).join(filename);
-        let mut file = BufReader::new(File::open(crate_path)?);
+        Ok(self.serato_path().join(SUBCRATE_DIR).join(filename))
+    }
+
+    /// Read the subcrate with the given name.
+    pub fn read_subcrate(&self, name: &str) -> Result<Crate, Error> {
+        let mut file = BufReader::new(File::open(self.subcrate_path(name)?)?);
         let mut data = vec![];
         file.read_to_end(&mut data)?;
 
         let fields = database::parse(&data)?;
-        let tracks = fields.into_iter().filter_map(move |field| {
-            if let database::Field::Track(track_fields) = field {
-                for track_field in track_fields {
-                    if let database::Field::TrackPath(path) = track_field {
-                        return self.track(&path);
-                    }
-                }
-            }
+        Crate::from_fields(fields)
+    }
 
-            None
-        });
+    /// Write the subcrate with the given name, replacing it if it already exists.
+    ///
+    /// The crate is written to a temporary file first, so that an existing crate file is left
+    /// intact if writing fails.
+    pub fn write_subcrate(&self, name: &str, krate: &Crate) -> Result<(), Error> {
+        let path = self.subcrate_path(name)?;
+        fs::create_dir_all(self.serato_path().join(SUBCRATE_DIR))?;
+        let mut data = Vec::new();
+        database::write(&mut data, &krate.to_fields())?;
+        replace_file(&path, &data)
+    }
+
+    /// Get a list of tracks from the subcrate with the given name.
+    ///
+    /// Tracks that are not present in the library database are skipped.
+    pub fn subcrate(&self, name: &str) -> Result<impl Iterator<Item = &Track>, Error> {
+        let tracks = self
+            .read_subcrate(name)?
+            .track_paths
+            .into_iter()
+            .filter_map(move |path| self.track(&path));
         Ok(tracks)
     }
+
+    /// Get the paths of all tracks in the subcrate with the given name (in crate order).
+    ///
+    /// In contrast to [`Library::subcrate`], this also returns paths that are not present in the
+    /// library database.
+    pub fn subcrate_track_paths(&self, name: &str) -> Result<Vec<PathBuf>, Error> {
+        Ok(self.read_subcrate(name)?.track_paths)
+    }
+
+    /// Create a new, empty subcrate with the given name.
+    ///
+    /// The crate file is created with the same default sorting and columns that Serato DJ uses for
+    /// new crates. Fails if a subcrate with that name already exists.
+    pub fn create_subcrate(&self, name: &str) -> Result<(), Error> {
+        if self.subcrate_path(name)?.exists() {
+            return Err(Error::IOError(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "subcrate already exists",
+            )));
+        }
+
+        self.write_subcrate(name, &Crate::new())
+    }
+
+    /// Delete the subcrate with the given name.
+    ///
+    /// This only removes the crate file, the tracks themselves are not touched.
+    pub fn delete_subcrate(&self, name: &str) -> Result<(), Error> {
+        fs::remove_file(self.subcrate_path(name)?)?;
+        Ok(())
+    }
+
+    /// Replace the tracks of the subcrate with the given name.
+    ///
+    /// All other crate fields (sorting, columns, etc.) are preserved. If the subcrate does not
+    /// exist yet, it will be created.
+    pub fn set_subcrate_tracks(&self, name: &str, track_paths: &[PathBuf]) -> Result<(), Error> {
+        let mut krate = match self.read_subcrate(name) {
+            Ok(krate) => krate,
+            Err(Error::IOError(err)) if err.kind() == io::ErrorKind::NotFound => Crate::new(),
+            Err(err) => return Err(err),
+        };
+        krate.track_paths = track_paths.to_vec();
+        self.write_subcrate(name, &krate)
+    }
+
+    /// Append a track to the end of the subcrate with the given name.
+    ///
+    /// If the track is already part of the crate, nothing happens.
+    pub fn add_subcrate_track(&self, name: &str, track_path: &Path) -> Result<(), Error> {
+        let mut krate = self.read_subcrate(name)?;
+        if krate.track_paths.iter().any(|path| path == track_path) {
+            return Ok(());
+        }
+
+        krate.track_paths.push(track_path.to_owned());
+        self.write_subcrate(name, &krate)
+    }
+
+    /// Remove a track from the subcrate with the given name.
+    pub fn remove_subcrate_track(&self, name: &str, track_path: &Path) -> Result<(), Error> {
+        let mut krate = self.read_subcrate(name)?;
+        krate.track_paths.retain(|path| path != track_path);
+        self.write_subcrate(name, &krate)
+    }
+
+    /// Move the track at position `from` to position `to` in the subcrate with the given name.
+    pub fn move_subcrate_track(&self, name: &str, from: usize, to: usize) -> Result<(), Error> {
+        let mut krate = self.read_subcrate(name)?;
+        if from >= krate.track_paths.len() || to >= krate.track_paths.len() {
+            return Err(Error::IOError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "track index out of range",
+            )));
+        }
+
+        let track_path = krate.track_paths.remove(from);
+        krate.track_paths.insert(to, track_path);
+        self.write_subcrate(name, &krate)
+    }
 }
 
-fn crate_name_from_path(path: &PathBuf) -> Result<String, Error> {
-    if !path.is_file() {
+/// Sorts the subcrate nodes and all of their descendants by name.
+fn sort_subcrate_nodes(nodes: &mut [SubcrateNode]) {
+    nodes.sort_by(|a, b| a.name().cmp(b.name()));
+    for node in nodes {
+        sort_subcrate_nodes(&mut node.children);
+    }
+}
+
+/// Returns the full name of the subcrate `name` inside the `parent` subcrate.
+///
+/// Fails if `name` is empty or contains `%%`, or if one of the components of `parent` is empty.
+fn nested_subcrate_name(parent: Option<&str>, name: &str) -> Result<String, Error> {
+    if name.is_empty() || name.contains(SUBCRATE_SEPARATOR) {
         return Err(Error::IOError(io::Error::new(
-            io::ErrorKind::Other,
-            "crate path is not a file",
+            io::ErrorKind::InvalidInput,
+            "invalid subcrate name",
         )));
     }
 
+    if let Some(parent) = parent {
+        if parent.split(SUBCRATE_SEPARATOR).any(str::is_empty) {
+            return Err(Error::IOError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid parent subcrate name",
+            )));
+        }
+    }
+
+    Ok(match parent {
+        Some(parent) => format!("{}{}{}", parent, SUBCRATE_SEPARATOR, name),
+        None => name.to_string(),
+    })
+}
+
+fn crate_name_from_path(path: &Path) -> Result<String, Error> {
+    if !path.is_file() {
+        return Err(Error::IOError(io::Error::other("crate path is not a file")));
+    }
+
     if let Some(ext) = path.extension() {
         if ext != CRATE_EXTENSION {
-            return Err(Error::IOError(io::Error::new(
-                io::ErrorKind::Other,
+            return Err(Error::IOError(io::Error::other(
                 "crate path has no .crate extension",
             )));
         }
@@ -229,8 +964,7 @@ fn crate_name_from_path(path: &PathBuf) -> Result<String, Error> {
         }
     }
 
-    Err(Error::IOError(io::Error::new(
-        io::ErrorKind::Other,
+    Err(Error::IOError(io::Error::other(
         "Failed to create crate name",
     )))
 }
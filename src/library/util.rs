@@ -0,0 +1,225 @@
+//! Helpers for parsing and formatting the text values in the library database.
+//!
+//! Serato stores many numeric values (e.g. BPM, bitrate or track length) as human-readable UTF-16
+//! strings. These functions convert between those strings and typed values.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Units used for file sizes, in ascending order.
+const FILE_SIZE_UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+/// Returns a `f64` parsed from the text, ignoring surrounding whitespace.
+fn parse_number(text: &str) -> Option<f64> {
+    let value = text.trim().parse::<f64>().ok()?;
+    if value.is_finite() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Strips the given (case-insensitive) suffix from the text.
+fn strip_suffix_ignore_case<'a>(text: &'a str, suffix: &str) -> Option<&'a str> {
+    let split_index = text.len().checked_sub(suffix.len())?;
+    if !text.is_char_boundary(split_index) {
+        return None;
+    }
+    let (value, unit) = text.split_at(split_index);
+    if unit.eq_ignore_ascii_case(suffix) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Parses a BPM value (e.g. `128.00`).
+pub fn parse_bpm(text: &str) -> Option<f64> {
+    parse_number(text)
+}
+
+/// Formats a BPM value the way Serato does (e.g. `128.00`).
+pub fn format_bpm(bpm: f64) -> String {
+    format!("{:.2}", bpm)
+}
+
+#[test]
+fn test_bpm() {
+    assert_eq!(parse_bpm("128.00"), Some(128.0));
+    assert_eq!(parse_bpm(" 93.5 "), Some(93.5));
+    assert_eq!(parse_bpm("fast"), None);
+    assert_eq!(format_bpm(128.0), "128.00");
+    assert_eq!(format_bpm(87.456), "87.46");
+}
+
+/// Parses a bitrate in kbps (e.g. `320.0kbps`).
+pub fn parse_bitrate(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let value = strip_suffix_ignore_case(text, "kbps").unwrap_or(text);
+    parse_number(value)
+}
+
+/// Formats a bitrate in kbps the way Serato does (e.g. `320.0kbps`).
+pub fn format_bitrate(kbps: f64) -> String {
+    format!("{:.1}kbps", kbps)
+}
+
+#[test]
+fn test_bitrate() {
+    assert_eq!(parse_bitrate("320.0kbps"), Some(320.0));
+    assert_eq!(parse_bitrate("192 KBPS"), Some(192.0));
+    assert_eq!(parse_bitrate("128"), Some(128.0));
+    assert_eq!(parse_bitrate("kbps"), None);
+    assert_eq!(format_bitrate(320.0), "320.0kbps");
+}
+
+/// Parses a sample rate and returns it in Hz (e.g. `44.1k` becomes `44100`).
+pub fn parse_sample_rate(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let hz = match strip_suffix_ignore_case(text, "k") {
+        Some(value) => parse_number(value)? * 1000.0,
+        None => parse_number(strip_suffix_ignore_case(text, "hz").unwrap_or(text))?,
+    };
+    if hz < 0.0 || hz > u32::MAX as f64 {
+        return None;
+    }
+    Some(hz.round() as u32)
+}
+
+/// Formats a sample rate in Hz the way Serato does (e.g. `44100` becomes `44.1k`).
+pub fn format_sample_rate(hz: u32) -> String {
+    format!("{:.1}k", hz as f64 / 1000.0)
+}
+
+#[test]
+fn test_sample_rate() {
+    assert_eq!(parse_sample_rate("44.1k"), Some(44100));
+    assert_eq!(parse_sample_rate("48.0K"), Some(48000));
+    assert_eq!(parse_sample_rate("96000"), Some(96000));
+    assert_eq!(parse_sample_rate("22050Hz"), Some(22050));
+    assert_eq!(parse_sample_rate("-1k"), None);
+    assert_eq!(parse_sample_rate("k"), None);
+    assert_eq!(format_sample_rate(44100), "44.1k");
+    assert_eq!(format_sample_rate(48000), "48.0k");
+}
+
+/// Parses a file size and returns it in bytes (e.g. `14.6MB` becomes `15309210`).
+///
+/// Serato uses binary prefixes, i.e. `1KB` equals 1024 bytes.
+pub fn parse_file_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    for (exponent, unit) in FILE_SIZE_UNITS.iter().enumerate().rev() {
+        if let Some(value) = strip_suffix_ignore_case(text, unit) {
+            let bytes = parse_number(value)? * 1024f64.powi(exponent as i32);
+            if bytes < 0.0 || bytes > u64::MAX as f64 {
+                return None;
+            }
+            return Some(bytes.round() as u64);
+        }
+    }
+
+    None
+}
+
+/// Formats a file size in bytes the way Serato does (e.g. `15319300` becomes `14.6MB`).
+pub fn format_file_size(bytes: u64) -> String {
+    let mut value = bytes as f64;
+    let mut unit_index = 0;
+    while value >= 1024.0 && unit_index < FILE_SIZE_UNITS.len() - 1 {
+        value /= 1024.0;
+        unit_index += 1;
+    }
+    format!("{:.1}{}", value, FILE_SIZE_UNITS[unit_index])
+}
+
+#[test]
+fn test_file_size() {
+    assert_eq!(parse_file_size("14.6MB"), Some(15309210));
+    assert_eq!(parse_file_size("1.5 GB"), Some(1610612736));
+    assert_eq!(parse_file_size("512.0KB"), Some(524288));
+    assert_eq!(parse_file_size("100B"), Some(100));
+    assert_eq!(parse_file_size("14.6"), None);
+    assert_eq!(parse_file_size("MB"), None);
+    assert_eq!(format_file_size(15319300), "14.6MB");
+    assert_eq!(format_file_size(1610612736), "1.5GB");
+    assert_eq!(format_file_size(100), "100.0B");
+}
+
+/// Parses a track length (e.g. `03:45.12` or `1:02:03.45`).
+pub fn parse_length(text: &str) -> Option<Duration> {
+    let mut components = text.trim().rsplit(':');
+    let seconds = parse_number(components.next()?)?;
+    let mut total_seconds = seconds;
+    let mut factor = 60.0;
+    for component in components {
+        if factor > 3600.0 {
+            return None;
+        }
+        let value = component.trim().parse::<u32>().ok()?;
+        total_seconds += value as f64 * factor;
+        factor *= 60.0;
+    }
+    // Fails for negative values and values that are too large for a `Duration`.
+    Duration::try_from_secs_f64(total_seconds).ok()
+}
+
+/// Formats a track length the way Serato does (e.g. `03:45.12`).
+///
+/// Hours are not used, i.e. tracks longer than an hour have more than 59 minutes.
+pub fn format_length(length: Duration) -> String {
+    let centis = (length.as_millis() + 5) / 10;
+    let minutes = centis / 6000;
+    let seconds = (centis / 100) % 60;
+    format!("{:02}:{:02}.{:02}", minutes, seconds, centis % 100)
+}
+
+#[test]
+fn test_length() {
+    assert_eq!(
+        parse_length("03:45.12"),
+        Some(Duration::from_millis(225120))
+    );
+    assert_eq!(
+        parse_length("06:22.93"),
+        Some(Duration::from_millis(382930))
+    );
+    assert_eq!(
+        parse_length("1:02:03.45"),
+        Some(Duration::from_millis(3723450))
+    );
+    assert_eq!(parse_length("42"), Some(Duration::from_secs(42)));
+    assert_eq!(parse_length("1:2:3:4"), None);
+    assert_eq!(parse_length("ab:cd"), None);
+    assert_eq!(parse_length("-1"), None);
+    assert_eq!(parse_length("1e30"), None);
+    assert_eq!(format_length(Duration::from_millis(225120)), "03:45.12");
+    assert_eq!(format_length(Duration::from_millis(3723450)), "62:03.45");
+    assert_eq!(format_length(Duration::from_millis(59999)), "01:00.00");
+}
+
+/// Converts a UNIX timestamp to a `SystemTime`.
+pub fn timestamp_to_system_time(timestamp: u32) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(timestamp.into())
+}
+
+/// Converts a `SystemTime` to a UNIX timestamp, or returns `None` if it is out of range.
+pub fn system_time_to_timestamp(time: SystemTime) -> Option<u32> {
+    let seconds = time.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    if seconds > u32::MAX.into() {
+        return None;
+    }
+    Some(seconds as u32)
+}
+
+#[test]
+fn test_timestamp() {
+    let time = timestamp_to_system_time(1580992490);
+    assert_eq!(
+        time.duration_since(UNIX_EPOCH).unwrap(),
+        Duration::from_secs(1580992490)
+    );
+    assert_eq!(system_time_to_timestamp(time), Some(1580992490));
+    assert_eq!(
+        system_time_to_timestamp(UNIX_EPOCH - Duration::from_secs(1)),
+        None
+    );
+}
@@ -5,13 +5,14 @@ use super::generic::Version;
 use super::util::{take_version, write_version};
 use crate::error::Error;
 use crate::util::Res;
+use std::convert::TryFrom;
 use std::io;
 
 /// Represents the terminal beatgrid marker in the `Serato BeatGrid` tag.
 ///
 /// The last beatgrid marker always has to be a terminal one. This is also the case if the tag only
 /// contains a single beatgrid marker.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TerminalMarker {
     /// The position in seconds.
     pub position: f32,
@@ -22,7 +23,7 @@ pub struct TerminalMarker {
 /// Represents a non-terminal beatgrid marker in the `Serato BeatGrid` tag.
 ///
 /// All beatgrid markers before the last one are non-terminal beatgrid markers.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NonTerminalMarker {
     /// The position in seconds.
     pub position: f32,
@@ -47,7 +48,7 @@ pub struct NonTerminalMarker {
 ///     println!("{:?}", content);
 /// }
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Beatgrid {
     /// The analysis version.
     pub version: Version,
@@ -59,6 +60,503 @@ pub struct Beatgrid {
     pub footer: u8,
 }
 
+/// Tag version written by [`Beatgrid::new`].
+const BEATGRID_VERSION: Version = Version { major: 1, minor: 0 };
+
+/// Number of beats per bar.
+///
+/// Serato assumes a 4/4 time signature for all tracks.
+pub const BEATS_PER_BAR: u32 = 4;
+
+/// A section of the beatgrid with constant tempo.
+///
+/// Each beatgrid marker starts a new segment, and the segment of the terminal marker extends to the
+/// end of the track.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BeatgridSegment {
+    /// The position of the segment's first beat in seconds.
+    pub position: f64,
+    /// The number of the segment's first beat (the first beatgrid marker is beat 0).
+    pub first_beat: i64,
+    /// The number of the segment's first bar (the first beatgrid marker starts bar 0).
+    pub first_bar: i64,
+    /// The number of beats in this segment (`None` for the terminal segment).
+    pub beats: Option<u32>,
+    /// The tempo of this segment in beats per minute (BPM).
+    pub bpm: f64,
+}
+
+impl BeatgridSegment {
+    /// Returns the length of a single beat in seconds.
+    pub fn beat_length(&self) -> f64 {
+        60.0 / self.bpm
+    }
+}
+
+/// A single beat of the beatgrid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beat {
+    /// The beat number (the first beatgrid marker is beat 0, beats before it are negative).
+    pub index: i64,
+    /// The position in seconds.
+    pub position: f64,
+    /// The bar number (the first beatgrid marker starts bar 0, bars before it are negative).
+    pub bar: i64,
+    /// The position of the beat in its bar (`0` is the downbeat).
+    pub beat_in_bar: u32,
+}
+
+impl Beat {
+    /// Returns `true` if this beat is the first beat of a bar.
+    pub fn is_downbeat(&self) -> bool {
+        self.beat_in_bar == 0
+    }
+}
+
+/// The resolution used by [`Beatgrid::quantize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantization {
+    /// Snap to the nearest downbeat.
+    Bar,
+    /// Snap to the nearest beat.
+    Beat,
+    /// Snap to the nearest 1/2 beat.
+    HalfBeat,
+    /// Snap to the nearest 1/4 beat.
+    QuarterBeat,
+}
+
+/// Iterator over the beats of a [`Beatgrid`], returned by [`Beatgrid::beats`].
+#[derive(Debug, Clone)]
+pub struct Beats {
+    segments: Vec<BeatgridSegment>,
+    next_index: i64,
+    last_position: Option<f64>,
+    end_position: f64,
+}
+
+impl Iterator for Beats {
+    type Item = Beat;
+
+    fn next(&mut self) -> Option<Beat> {
+        let beat = beat_in_segments(&self.segments, self.next_index);
+        if beat.position > self.end_position {
+            return None;
+        }
+        // Stop if the beatgrid is broken and the positions don't increase anymore, otherwise
+        // this would never terminate.
+        if beat.position.is_nan()
+            || matches!(self.last_position, Some(position) if beat.position <= position)
+        {
+            return None;
+        }
+        self.last_position = Some(beat.position);
+        self.next_index += 1;
+        Some(beat)
+    }
+}
+
+/// Returns the segment that contains the given beat number (or the first segment for beats before
+/// the first marker).
+fn segment_of_beat(segments: &[BeatgridSegment], beat: f64) -> &BeatgridSegment {
+    segments
+        .iter()
+        .rev()
+        .find(|segment| segment.first_beat as f64 <= beat)
+        .unwrap_or(&segments[0])
+}
+
+/// Returns the segment that contains the given position (or the first segment for positions
+/// before the first marker).
+fn segment_at(segments: &[BeatgridSegment], position: f64) -> &BeatgridSegment {
+    segments
+        .iter()
+        .rev()
+        .find(|segment| segment.position <= position)
+        .unwrap_or(&segments[0])
+}
+
+fn segment_beat_at(segments: &[BeatgridSegment], position: f64) -> f64 {
+    let segment = segment_at(segments, position);
+    segment.first_beat as f64 + (position - segment.position) / segment.beat_length()
+}
+
+fn time_in_segments(segments: &[BeatgridSegment], beat: f64) -> f64 {
+    let segment = segment_of_beat(segments, beat);
+    segment.position + (beat - segment.first_beat as f64) * segment.beat_length()
+}
+
+fn beat_in_segments(segments: &[BeatgridSegment], index: i64) -> Beat {
+    let segment = segment_of_beat(segments, index as f64);
+    let beats_per_bar = i64::from(BEATS_PER_BAR);
+    let offset = index - segment.first_beat;
+    Beat {
+        index,
+        position: time_in_segments(segments, index as f64),
+        bar: segment.first_bar + offset.div_euclid(beats_per_bar),
+        beat_in_bar: offset.rem_euclid(beats_per_bar) as u32,
+    }
+}
+
+fn invalid_beatgrid(reason: &'static str) -> Error {
+    Error::InvalidBeatgrid { reason }
+}
+
+/// Convert a (rounded) number of beats to an integer without saturating.
+fn beats_to_i64(beats: f64) -> Result<i64, Error> {
+    if !beats.is_finite() || beats < i64::MIN as f64 || beats >= i64::MAX as f64 {
+        return Err(invalid_beatgrid("too many beats between markers"));
+    }
+    Ok(beats as i64)
+}
+
+impl Beatgrid {
+    /// Create a beatgrid with constant tempo, starting at `position` seconds.
+    pub fn new(position: f32, bpm: f32) -> Self {
+        Self {
+            version: BEATGRID_VERSION,
+            non_terminal_markers: Vec::new(),
+            terminal_marker: TerminalMarker { position, bpm },
+            footer: 0,
+        }
+    }
+
+    /// Returns the number of beatgrid markers (including the terminal marker).
+    pub fn marker_count(&self) -> usize {
+        self.non_terminal_markers.len() + 1
+    }
+
+    /// Returns the position of the marker with the given index.
+    fn marker_position_mut(&mut self, index: usize) -> Option<&mut f32> {
+        if index == self.non_terminal_markers.len() {
+            return Some(&mut self.terminal_marker.position);
+        }
+        self.non_terminal_markers
+            .get_mut(index)
+            .map(|marker| &mut marker.position)
+    }
+
+    /// Check that the beatgrid can be safely written and used by Serato.
+    ///
+    /// The number of markers has to fit into the tag, all positions have to be finite and strictly
+    /// increasing, each non-terminal marker has to span at least one beat and the BPM of the
+    /// terminal marker has to be positive.
+    pub fn validate(&self) -> Result<(), Error> {
+        if u32::try_from(self.marker_count()).is_err() {
+            return Err(invalid_beatgrid("too many markers"));
+        }
+
+        let mut previous_position = None;
+        for marker in &self.non_terminal_markers {
+            if !marker.position.is_finite() {
+                return Err(invalid_beatgrid("marker position is not finite"));
+            }
+            if matches!(previous_position, Some(position) if marker.position <= position) {
+                return Err(invalid_beatgrid("marker positions are not increasing"));
+            }
+            if marker.beats_till_next_marker == 0 {
+                return Err(invalid_beatgrid("marker without beats"));
+            }
+            previous_position = Some(marker.position);
+        }
+
+        let terminal_marker = &self.terminal_marker;
+        if !terminal_marker.position.is_finite() {
+            return Err(invalid_beatgrid("marker position is not finite"));
+        }
+        if matches!(previous_position, Some(position) if terminal_marker.position <= position) {
+            return Err(invalid_beatgrid("marker positions are not increasing"));
+        }
+        if !terminal_marker.bpm.is_finite() || terminal_marker.bpm <= 0.0 {
+            return Err(invalid_beatgrid("BPM is not positive"));
+        }
+        Ok(())
+    }
+
+    /// Insert a new marker at `position` seconds and return its index.
+    ///
+    /// If the position is between two existing markers, the beats of that segment are split at
+    /// the beat closest to `position`. Otherwise, the number of beats to the adjacent marker is
+    /// derived from the tempo of the first or last segment. Fails if the beatgrid is not
+    /// [valid](Self::validate).
+    pub fn insert_marker(&mut self, position: f32) -> Result<usize, Error> {
+        self.validate()?;
+        if !position.is_finite() {
+            return Err(invalid_beatgrid("marker position is not finite"));
+        }
+        let segments = self.segments();
+        let index = segments
+            .iter()
+            .filter(|segment| segment.position < f64::from(position))
+            .count();
+        if matches!(segments.get(index), Some(segment) if segment.position == f64::from(position)) {
+            return Err(invalid_beatgrid("marker already exists"));
+        }
+
+        if index == 0 {
+            // Insert before the first marker.
+            let beats = beats_to_i64(self.beat_at(f64::from(position)).round())?.min(-1);
+            let beats_till_next_marker = beats
+                .checked_neg()
+                .and_then(|beats| u32::try_from(beats).ok())
+                .ok_or_else(|| invalid_beatgrid("too many beats between markers"))?;
+            self.non_terminal_markers.insert(
+                0,
+                NonTerminalMarker {
+                    position,
+                    beats_till_next_marker,
+                },
+            );
+        } else if index == segments.len() {
+            // Insert after the terminal marker, which becomes a non-terminal one.
+            let terminal_segment = &segments[index - 1];
+            let beats = (self.beat_at(f64::from(position)) - terminal_segment.first_beat as f64)
+                .round()
+                .max(1.0);
+            let beats_till_next_marker = u32::try_from(beats_to_i64(beats)?)
+                .map_err(|_| invalid_beatgrid("too many beats between markers"))?;
+            self.non_terminal_markers.push(NonTerminalMarker {
+                position: self.terminal_marker.position,
+                beats_till_next_marker,
+            });
+            self.terminal_marker.position = position;
+        } else {
+            // Split the segment of the previous marker.
+            let offset =
+                (self.beat_at(f64::from(position)) - segments[index - 1].first_beat as f64).round();
+            let marker = &mut self.non_terminal_markers[index - 1];
+            let beats = marker.beats_till_next_marker;
+            if beats < 2 {
+                return Err(invalid_beatgrid("no beat between markers"));
+            }
+            let offset = (offset.max(1.0) as u32).min(beats - 1);
+            marker.beats_till_next_marker = offset;
+            self.non_terminal_markers.insert(
+                index,
+                NonTerminalMarker {
+                    position,
+                    beats_till_next_marker: beats - offset,
+                },
+            );
+        }
+        Ok(index)
+    }
+
+    /// Remove the marker with the given index.
+    ///
+    /// The beats of the removed marker's segment are added to the previous segment. If the
+    /// terminal marker is removed, the previous marker becomes the terminal marker (using the tempo
+    /// of its segment). The last remaining marker can't be removed. Fails if the beatgrid is not
+    /// [valid](Self::validate).
+    pub fn remove_marker(&mut self, index: usize) -> Result<(), Error> {
+        self.validate()?;
+        let count = self.marker_count();
+        if index >= count {
+            return Err(invalid_beatgrid("marker index out of range"));
+        }
+        if count == 1 {
+            return Err(invalid_beatgrid("beatgrid needs at least one marker"));
+        }
+
+        if index == count - 1 {
+            let bpm = self.segments()[index - 1].bpm as f32;
+            if let Some(marker) = self.non_terminal_markers.pop() {
+                self.terminal_marker = TerminalMarker {
+                    position: marker.position,
+                    bpm,
+                };
+            }
+        } else {
+            let marker = self.non_terminal_markers.remove(index);
+            if index > 0 {
+                let previous_marker = &mut self.non_terminal_markers[index - 1];
+                previous_marker.beats_till_next_marker = previous_marker
+                    .beats_till_next_marker
+                    .checked_add(marker.beats_till_next_marker)
+                    .ok_or_else(|| invalid_beatgrid("too many beats between markers"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the marker with the given index to `position` seconds.
+    ///
+    /// The number of beats between the markers is preserved, i.e. the tempo of the adjacent
+    /// segments changes. If the resulting beatgrid would be invalid, it is left unchanged.
+    pub fn move_marker(&mut self, index: usize, position: f32) -> Result<(), Error> {
+        let marker_position = self
+            .marker_position_mut(index)
+            .ok_or_else(|| invalid_beatgrid("marker index out of range"))?;
+        let old_position = std::mem::replace(marker_position, position);
+        if let Err(err) = self.validate() {
+            if let Some(marker_position) = self.marker_position_mut(index) {
+                *marker_position = old_position;
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Shift the whole beatgrid by `offset` seconds.
+    ///
+    /// If the resulting beatgrid would be invalid, it is left unchanged.
+    pub fn nudge(&mut self, offset: f32) -> Result<(), Error> {
+        let mut beatgrid = self.clone();
+        for marker in &mut beatgrid.non_terminal_markers {
+            marker.position += offset;
+        }
+        beatgrid.terminal_marker.position += offset;
+        beatgrid.validate()?;
+        *self = beatgrid;
+        Ok(())
+    }
+
+    /// Double the tempo of the beatgrid, i.e. add a beat between each pair of beats.
+    ///
+    /// Fails if the beatgrid is not [valid](Self::validate).
+    pub fn double_tempo(&mut self) -> Result<(), Error> {
+        self.validate()?;
+        let beats = self
+            .non_terminal_markers
+            .iter()
+            .map(|marker| marker.beats_till_next_marker.checked_mul(2))
+            .collect::<Option<Vec<u32>>>()
+            .ok_or_else(|| invalid_beatgrid("too many beats between markers"))?;
+        for (marker, beats) in self.non_terminal_markers.iter_mut().zip(beats) {
+            marker.beats_till_next_marker = beats;
+        }
+        self.terminal_marker.bpm *= 2.0;
+        Ok(())
+    }
+
+    /// Halve the tempo of the beatgrid, i.e. remove every second beat.
+    ///
+    /// This fails if a segment contains an odd number of beats, because the markers would not be
+    /// on a beat anymore. It also fails if the beatgrid is not [valid](Self::validate).
+    pub fn halve_tempo(&mut self) -> Result<(), Error> {
+        self.validate()?;
+        if self
+            .non_terminal_markers
+            .iter()
+            .any(|marker| marker.beats_till_next_marker % 2 != 0)
+        {
+            return Err(invalid_beatgrid("odd number of beats between markers"));
+        }
+        for marker in &mut self.non_terminal_markers {
+            marker.beats_till_next_marker /= 2;
+        }
+        self.terminal_marker.bpm /= 2.0;
+        Ok(())
+    }
+
+    /// Returns the sections of constant tempo, one for each beatgrid marker.
+    ///
+    /// The tempo of a non-terminal segment is derived from the number of beats until the next
+    /// marker and the distance to it. Each marker starts a new bar, i.e. if the number of beats in
+    /// a segment is not a multiple of [`BEATS_PER_BAR`], its last bar is shorter.
+    pub fn segments(&self) -> Vec<BeatgridSegment> {
+        let mut segments = Vec::with_capacity(self.non_terminal_markers.len() + 1);
+        let mut first_beat = 0;
+        let mut first_bar = 0;
+        for (i, marker) in self.non_terminal_markers.iter().enumerate() {
+            let next_position = match self.non_terminal_markers.get(i + 1) {
+                Some(next_marker) => next_marker.position,
+                None => self.terminal_marker.position,
+            };
+            let beats = marker.beats_till_next_marker;
+            let position = f64::from(marker.position);
+            segments.push(BeatgridSegment {
+                position,
+                first_beat,
+                first_bar,
+                beats: Some(beats),
+                bpm: 60.0 * f64::from(beats) / (f64::from(next_position) - position),
+            });
+            first_beat += i64::from(beats);
+            first_bar += i64::from(beats.div_ceil(BEATS_PER_BAR));
+        }
+        segments.push(BeatgridSegment {
+            position: f64::from(self.terminal_marker.position),
+            first_beat,
+            first_bar,
+            beats: None,
+            bpm: f64::from(self.terminal_marker.bpm),
+        });
+        segments
+    }
+
+    /// Returns the (fractional) beat number at the given position in seconds.
+    ///
+    /// Positions before the first marker are extrapolated using the tempo of the first segment.
+    pub fn beat_at(&self, position: f64) -> f64 {
+        segment_beat_at(&self.segments(), position)
+    }
+
+    /// Returns the position in seconds of the given (fractional) beat number.
+    pub fn time_of_beat(&self, beat: f64) -> f64 {
+        time_in_segments(&self.segments(), beat)
+    }
+
+    /// Returns the tempo (in BPM) at the given position in seconds.
+    pub fn bpm_at(&self, position: f64) -> f64 {
+        segment_at(&self.segments(), position).bpm
+    }
+
+    /// Returns the beat with the given number.
+    pub fn beat(&self, index: i64) -> Beat {
+        beat_in_segments(&self.segments(), index)
+    }
+
+    /// Returns the position (in seconds) on the grid that is closest to `position`.
+    pub fn quantize(&self, position: f64, quantization: Quantization) -> f64 {
+        let segments = self.segments();
+        let beat = segment_beat_at(&segments, position);
+        let step = match quantization {
+            Quantization::Beat => 1.0,
+            Quantization::HalfBeat => 0.5,
+            Quantization::QuarterBeat => 0.25,
+            Quantization::Bar => {
+                // Bars can be shorter at the end of a segment, so the next downbeat is not
+                // necessarily `BEATS_PER_BAR` beats away.
+                let index = beat.floor() as i64;
+                let bar_start = index - i64::from(beat_in_segments(&segments, index).beat_in_bar);
+                let next_bar_start = segments
+                    .iter()
+                    .map(|segment| segment.first_beat)
+                    .find(|&first_beat| first_beat > bar_start)
+                    .unwrap_or(i64::MAX)
+                    .min(bar_start + i64::from(BEATS_PER_BAR));
+                let previous = time_in_segments(&segments, bar_start as f64);
+                let next = time_in_segments(&segments, next_bar_start as f64);
+                return if position - previous <= next - position {
+                    previous
+                } else {
+                    next
+                };
+            }
+        };
+        time_in_segments(&segments, (beat / step).round() * step)
+    }
+
+    /// Returns an iterator over all beats from the start of the track up to `track_length`
+    /// seconds.
+    ///
+    /// If the first marker is not at the start of the track, the grid is extended backwards using
+    /// the tempo of the first segment.
+    pub fn beats(&self, track_length: f64) -> Beats {
+        let segments = self.segments();
+        let first_segment = &segments[0];
+        let next_index = first_segment.first_beat
+            + (-first_segment.position / first_segment.beat_length()).ceil() as i64;
+        Beats {
+            segments,
+            next_index,
+            last_position: None,
+            end_position: track_length,
+        }
+    }
+}
+
 impl Tag for Beatgrid {
     const NAME: &'static str = "Serato BeatGrid";
 
@@ -68,7 +566,7 @@ impl Tag for Beatgrid {
     }
 
     fn write(&self, writer: impl io::Write) -> Result<usize, Error> {
-        write_beatgrid(writer, &self)
+        write_beatgrid(writer, self)
     }
 }
 
@@ -126,7 +624,7 @@ fn take_terminal_marker(input: &[u8]) -> Res<&[u8], TerminalMarker> {
 
 /// Take a [`Beatgrid` struct] parsed from the input slice.
 fn take_beatgrid(input: &[u8]) -> Res<&[u8], Beatgrid> {
-    let (input, version) = take_version(&input)?;
+    let (input, version) = take_version(input)?;
     let (input, non_terminal_markers) =
         nom::multi::length_count(take_non_terminal_marker_count, take_non_terminal_marker)(input)?;
     let (input, terminal_marker) = take_terminal_marker(input)?;
@@ -164,9 +662,246 @@ pub fn write_beatgrid(mut writer: impl io::Write, beatgrid: &Beatgrid) -> Result
     let num_markers = beatgrid.non_terminal_markers.len() as u32 + 1;
     bytes_written += writer.write(&num_markers.to_be_bytes())?;
     for marker in &beatgrid.non_terminal_markers {
-        bytes_written += write_non_terminal_marker(&mut writer, &marker)?;
+        bytes_written += write_non_terminal_marker(&mut writer, marker)?;
     }
     bytes_written += write_terminal_marker(&mut writer, &beatgrid.terminal_marker)?;
     bytes_written += writer.write(&[beatgrid.footer])?;
     Ok(bytes_written)
 }
+
+#[cfg(test)]
+fn test_beatgrid() -> Beatgrid {
+    Beatgrid {
+        version: Version { major: 1, minor: 0 },
+        non_terminal_markers: vec![
+            NonTerminalMarker {
+                position: 1.0,
+                beats_till_next_marker: 6,
+            },
+            NonTerminalMarker {
+                position: 4.0,
+                beats_till_next_marker: 4,
+            },
+        ],
+        terminal_marker: TerminalMarker {
+            position: 5.0,
+            bpm: 60.0,
+        },
+        footer: 0,
+    }
+}
+
+#[test]
+fn test_beatgrid_segments() {
+    let segments = test_beatgrid().segments();
+    assert_eq!(segments.len(), 3);
+    assert_eq!(segments[0].bpm, 120.0);
+    assert_eq!(segments[1].bpm, 240.0);
+    assert_eq!(segments[1].first_beat, 6);
+    assert_eq!(segments[1].first_bar, 2);
+    assert_eq!(segments[2].first_beat, 10);
+    assert_eq!(segments[2].first_bar, 3);
+    assert_eq!(segments[2].beats, None);
+}
+
+#[test]
+fn test_beatgrid_beat_positions() {
+    let beatgrid = test_beatgrid();
+    assert_eq!(beatgrid.beat_at(0.0), -2.0);
+    assert_eq!(beatgrid.beat_at(2.25), 2.5);
+    assert_eq!(beatgrid.beat_at(4.5), 8.0);
+    assert_eq!(beatgrid.beat_at(7.0), 12.0);
+    assert_eq!(beatgrid.time_of_beat(-1.0), 0.5);
+    assert_eq!(beatgrid.time_of_beat(6.0), 4.0);
+    assert_eq!(beatgrid.time_of_beat(10.5), 5.5);
+    assert_eq!(beatgrid.bpm_at(0.0), 120.0);
+    assert_eq!(beatgrid.bpm_at(4.0), 240.0);
+    assert_eq!(beatgrid.bpm_at(100.0), 60.0);
+}
+
+#[test]
+fn test_beatgrid_beats() {
+    let beats: Vec<Beat> = test_beatgrid().beats(6.0).collect();
+    let positions: Vec<f64> = beats.iter().map(|beat| beat.position).collect();
+    assert_eq!(
+        positions,
+        vec![0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.25, 4.5, 4.75, 5.0, 6.0]
+    );
+    assert_eq!(beats[0].index, -2);
+    assert_eq!((beats[0].bar, beats[0].beat_in_bar), (-1, 2));
+    assert!(beats[2].is_downbeat());
+    // The first segment has 6 beats, so the second bar is only two beats long.
+    assert!(beats[6].is_downbeat());
+    assert_eq!((beats[7].bar, beats[7].beat_in_bar), (1, 1));
+    assert!(beats[8].is_downbeat());
+    assert_eq!(beats[8].bar, 2);
+    assert_eq!(beats[12], test_beatgrid().beat(10));
+    assert!(beats[12].is_downbeat());
+}
+
+#[test]
+fn test_beatgrid_beats_invalid() {
+    let mut beatgrid = test_beatgrid();
+    beatgrid.terminal_marker.bpm = 0.0;
+    assert_eq!(beatgrid.beats(100.0).count(), 12);
+}
+
+#[test]
+fn test_beatgrid_new() {
+    let beatgrid = Beatgrid::new(0.25, 128.0);
+    assert!(beatgrid.validate().is_ok());
+    assert_eq!(beatgrid.marker_count(), 1);
+
+    let mut output = Vec::new();
+    beatgrid.write(&mut output).unwrap();
+    let parsed = Beatgrid::parse(&output).unwrap();
+    assert_eq!(parsed.version, BEATGRID_VERSION);
+    assert_eq!(parsed.terminal_marker.position, 0.25);
+    assert_eq!(parsed.terminal_marker.bpm, 128.0);
+
+    assert!(Beatgrid::new(0.25, 0.0).validate().is_err());
+    assert!(Beatgrid::new(f32::NAN, 128.0).validate().is_err());
+}
+
+#[test]
+fn test_beatgrid_validate() {
+    assert!(test_beatgrid().validate().is_ok());
+
+    let mut beatgrid = test_beatgrid();
+    beatgrid.non_terminal_markers[1].position = 0.5;
+    assert!(beatgrid.validate().is_err());
+
+    let mut beatgrid = test_beatgrid();
+    beatgrid.terminal_marker.position = 4.0;
+    assert!(beatgrid.validate().is_err());
+
+    let mut beatgrid = test_beatgrid();
+    beatgrid.non_terminal_markers[0].beats_till_next_marker = 0;
+    assert!(beatgrid.validate().is_err());
+}
+
+#[test]
+fn test_beatgrid_insert_marker() {
+    let mut beatgrid = test_beatgrid();
+    assert_eq!(beatgrid.insert_marker(2.6).unwrap(), 1);
+    assert_eq!(beatgrid.non_terminal_markers[0].beats_till_next_marker, 3);
+    assert_eq!(beatgrid.non_terminal_markers[1].position, 2.6);
+    assert_eq!(beatgrid.non_terminal_markers[1].beats_till_next_marker, 3);
+
+    assert_eq!(beatgrid.insert_marker(0.0).unwrap(), 0);
+    assert_eq!(beatgrid.non_terminal_markers[0].beats_till_next_marker, 2);
+
+    assert_eq!(beatgrid.insert_marker(7.0).unwrap(), 5);
+    assert_eq!(beatgrid.non_terminal_markers[4].position, 5.0);
+    assert_eq!(beatgrid.non_terminal_markers[4].beats_till_next_marker, 2);
+    assert_eq!(beatgrid.terminal_marker.position, 7.0);
+    assert_eq!(beatgrid.terminal_marker.bpm, 60.0);
+    assert!(beatgrid.validate().is_ok());
+
+    assert!(beatgrid.insert_marker(4.0).is_err());
+    assert!(beatgrid.insert_marker(f32::INFINITY).is_err());
+    assert_eq!(beatgrid.marker_count(), 6);
+}
+
+#[test]
+fn test_beatgrid_edit_invalid() {
+    // The first segment has zero length, i.e. an infinite tempo.
+    let mut beatgrid = Beatgrid {
+        non_terminal_markers: vec![NonTerminalMarker {
+            position: 1.0,
+            beats_till_next_marker: 4,
+        }],
+        terminal_marker: TerminalMarker {
+            position: 1.0,
+            bpm: 120.0,
+        },
+        ..test_beatgrid()
+    };
+    assert!(beatgrid.insert_marker(0.0).is_err());
+    assert!(beatgrid.remove_marker(1).is_err());
+    assert!(beatgrid.double_tempo().is_err());
+    assert!(beatgrid.halve_tempo().is_err());
+    assert!(beatgrid.nudge(1.0).is_err());
+    assert_eq!(beatgrid.marker_count(), 2);
+    assert_eq!(beatgrid.terminal_marker.bpm, 120.0);
+}
+
+#[test]
+fn test_beatgrid_remove_marker() {
+    let mut beatgrid = test_beatgrid();
+    beatgrid.remove_marker(1).unwrap();
+    assert_eq!(beatgrid.non_terminal_markers.len(), 1);
+    assert_eq!(beatgrid.non_terminal_markers[0].beats_till_next_marker, 10);
+
+    beatgrid.remove_marker(1).unwrap();
+    assert!(beatgrid.non_terminal_markers.is_empty());
+    assert_eq!(beatgrid.terminal_marker.position, 1.0);
+    assert_eq!(beatgrid.terminal_marker.bpm, 150.0);
+
+    assert!(beatgrid.remove_marker(1).is_err());
+    assert!(beatgrid.remove_marker(0).is_err());
+
+    let mut beatgrid = test_beatgrid();
+    beatgrid.remove_marker(0).unwrap();
+    assert_eq!(beatgrid.non_terminal_markers[0].position, 4.0);
+}
+
+#[test]
+fn test_beatgrid_move_marker() {
+    let mut beatgrid = test_beatgrid();
+    assert!(beatgrid.move_marker(1, 0.5).is_err());
+    assert_eq!(beatgrid.non_terminal_markers[1].position, 4.0);
+    assert!(beatgrid.move_marker(3, 0.5).is_err());
+
+    beatgrid.move_marker(1, 3.0).unwrap();
+    assert_eq!(beatgrid.segments()[0].bpm, 180.0);
+    beatgrid.move_marker(2, 6.0).unwrap();
+    assert_eq!(beatgrid.terminal_marker.position, 6.0);
+
+    beatgrid.nudge(-0.5).unwrap();
+    assert_eq!(beatgrid.non_terminal_markers[0].position, 0.5);
+    assert_eq!(beatgrid.terminal_marker.position, 5.5);
+
+    assert!(beatgrid.nudge(f32::INFINITY).is_err());
+    assert!(beatgrid.nudge(f32::NAN).is_err());
+    assert_eq!(beatgrid.non_terminal_markers[0].position, 0.5);
+    assert_eq!(beatgrid.terminal_marker.position, 5.5);
+}
+
+#[test]
+fn test_beatgrid_change_tempo() {
+    let mut beatgrid = test_beatgrid();
+    beatgrid.double_tempo().unwrap();
+    assert_eq!(beatgrid.non_terminal_markers[0].beats_till_next_marker, 12);
+    assert_eq!(beatgrid.terminal_marker.bpm, 120.0);
+    assert_eq!(beatgrid.bpm_at(1.0), 240.0);
+
+    beatgrid.halve_tempo().unwrap();
+    beatgrid.halve_tempo().unwrap();
+    assert_eq!(beatgrid.non_terminal_markers[0].beats_till_next_marker, 3);
+    assert_eq!(beatgrid.terminal_marker.bpm, 30.0);
+    assert!(beatgrid.halve_tempo().is_err());
+    assert_eq!(beatgrid.terminal_marker.bpm, 30.0);
+}
+
+#[test]
+fn test_beatgrid_quantize() {
+    let beatgrid = test_beatgrid();
+    assert_eq!(beatgrid.quantize(1.2, Quantization::Beat), 1.0);
+    assert_eq!(beatgrid.quantize(1.3, Quantization::Beat), 1.5);
+    assert_eq!(beatgrid.quantize(1.3, Quantization::HalfBeat), 1.25);
+    assert_eq!(beatgrid.quantize(1.3, Quantization::QuarterBeat), 1.25);
+    assert_eq!(beatgrid.quantize(1.32, Quantization::QuarterBeat), 1.375);
+    assert_eq!(beatgrid.quantize(4.1, Quantization::Beat), 4.0);
+    assert_eq!(beatgrid.quantize(0.1, Quantization::Beat), 0.0);
+
+    // Bars start at beats 0, 4, 6 (second marker) and 10 (terminal marker).
+    assert_eq!(beatgrid.quantize(1.9, Quantization::Bar), 1.0);
+    assert_eq!(beatgrid.quantize(2.1, Quantization::Bar), 3.0);
+    assert_eq!(beatgrid.quantize(3.6, Quantization::Bar), 4.0);
+    assert_eq!(beatgrid.quantize(4.4, Quantization::Bar), 4.0);
+    assert_eq!(beatgrid.quantize(4.6, Quantization::Bar), 5.0);
+    assert_eq!(beatgrid.quantize(6.9, Quantization::Bar), 5.0);
+    assert_eq!(beatgrid.quantize(7.1, Quantization::Bar), 9.0);
+    assert_eq!(beatgrid.quantize(0.4, Quantization::Bar), 1.0);
+}
@@ -221,6 +221,126 @@ impl Color {
     }
 }
 
+/// The hotcue color palette of a Serato DJ variant (see the [module docs](self) for details).
+///
+/// Palette indices are zero-based, i.e. palette index 1 from the table above is `0`.
+///
+/// ```
+/// use triseratops::tag::color::{Color, HotcuePalette};
+///
+/// let palette = HotcuePalette::Pro;
+/// let stored_color = HotcuePalette::Intro.color(2).unwrap();
+/// assert_eq!(stored_color, Color::from(0xCC8800));
+/// assert_eq!(palette.displayed_color(stored_color), Color::from(0xF8821A));
+/// assert_eq!(palette.index_of(Color::from(0xF8821A)), Some(2));
+/// assert_eq!(palette.default_color(1), Some(Color::from(0xF8821A)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotcuePalette {
+    /// Serato DJ Intro, which displays the colors as they are stored in the metadata.
+    Intro,
+    /// Serato DJ Pro.
+    Pro,
+    /// Serato DJ Lite, which uses the Serato DJ Pro colors but only has 4 hotcues with fixed
+    /// colors.
+    Lite,
+}
+
+/// Number of colors in a [`HotcuePalette`].
+pub const HOTCUE_PALETTE_SIZE: usize = 18;
+
+/// Palette indices of the default colors for hotcues 1-8.
+const DEFAULT_HOTCUE_PALETTE_INDICES: [usize; 8] = [0, 2, 12, 3, 6, 15, 9, 14];
+
+impl HotcuePalette {
+    /// Returns the colors of the palette as displayed in this Serato DJ variant.
+    pub fn colors(self) -> &'static [Color; HOTCUE_PALETTE_SIZE] {
+        match self {
+            Self::Intro => &HOTCUE_COLORS_INTRO,
+            Self::Pro | Self::Lite => &HOTCUE_COLORS_PRO,
+        }
+    }
+
+    /// Returns the number of hotcues that this Serato DJ variant supports.
+    pub fn hotcue_count(self) -> usize {
+        match self {
+            Self::Lite => 4,
+            Self::Intro | Self::Pro => DEFAULT_HOTCUE_PALETTE_INDICES.len(),
+        }
+    }
+
+    /// Returns the (displayed) color for the given palette index.
+    pub fn color(self, palette_index: usize) -> Option<Color> {
+        self.colors().get(palette_index).copied()
+    }
+
+    /// Returns the palette index of the given (displayed) color.
+    pub fn index_of(self, color: Color) -> Option<usize> {
+        self.colors().iter().position(|&x| x == color)
+    }
+
+    /// Returns the palette index of the (displayed) color that is closest to the given color.
+    ///
+    /// ```
+    /// use triseratops::tag::color::{Color, HotcuePalette};
+    ///
+    /// assert_eq!(HotcuePalette::Intro.nearest_index(Color::from(0xFF0000)), 0);
+    /// assert_eq!(HotcuePalette::Pro.nearest_index(Color::from(0x00FFFF)), 9);
+    /// ```
+    pub fn nearest_index(self, color: Color) -> usize {
+        let distance = |other: &Color| {
+            let diff = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+            diff(color.red, other.red)
+                + diff(color.green, other.green)
+                + diff(color.blue, other.blue)
+        };
+
+        self.colors()
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, other)| distance(other))
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+
+    /// Returns the (displayed) palette color that is closest to the given color.
+    pub fn nearest_color(self, color: Color) -> Color {
+        self.colors()[self.nearest_index(color)]
+    }
+
+    /// Returns the default (displayed) color of the hotcue with the given index, or `None` if
+    /// this Serato DJ variant doesn't have that hotcue.
+    pub fn default_color(self, hotcue_index: u8) -> Option<Color> {
+        let hotcue_index = usize::from(hotcue_index);
+        if hotcue_index >= self.hotcue_count() {
+            return None;
+        }
+
+        self.color(DEFAULT_HOTCUE_PALETTE_INDICES[hotcue_index])
+    }
+
+    /// Returns the color that this Serato DJ variant displays for a color stored in the metadata.
+    ///
+    /// Colors that are not in the palette are returned unchanged.
+    pub fn displayed_color(self, stored_color: Color) -> Color {
+        match self {
+            Self::Intro => stored_color,
+            Self::Pro | Self::Lite => stored_color.into_pro_hotcue_color(),
+        }
+    }
+
+    /// Returns the color that needs to be stored in the metadata so that this Serato DJ variant
+    /// displays the given color.
+    ///
+    /// Colors that are not in the palette are returned unchanged.
+    pub fn stored_color(self, displayed_color: Color) -> Color {
+        match self {
+            Self::Intro => displayed_color,
+            Self::Pro | Self::Lite => displayed_color.into_intro_hotcue_color(),
+        }
+    }
+}
+
 const fn displayed_to_stored_track_color_code(code: u32) -> u32 {
     match code {
         0x090909 => 0x999999,
@@ -263,13 +383,13 @@ impl From<u32> for Color {
     }
 }
 
-impl Into<u32> for Color {
-    fn into(self) -> u32 {
-        (self.red as u32) << 16 | (self.green as u32) << 8 | self.blue as u32
+impl From<Color> for u32 {
+    fn from(val: Color) -> Self {
+        (val.red as u32) << 16 | (val.green as u32) << 8 | val.blue as u32
     }
 }
 
-const HOTCUE_COLORS_INTRO: [Color; 18] = [
+const HOTCUE_COLORS_INTRO: [Color; HOTCUE_PALETTE_SIZE] = [
     Color {
         red: 0xCC,
         green: 0x00,
@@ -362,7 +482,7 @@ const HOTCUE_COLORS_INTRO: [Color; 18] = [
     },
 ];
 
-const HOTCUE_COLORS_PRO: [Color; 18] = [
+const HOTCUE_COLORS_PRO: [Color; HOTCUE_PALETTE_SIZE] = [
     Color {
         red: 0xC0,
         green: 0x26,
@@ -1,8 +1,9 @@
 //! Provides the [`TagContainer` struct](TagContainer), a simple interface to access data in
 //! Serato's tags.
 use super::{
-    beatgrid, color::Color, format::flac::FLACTag, format::id3::ID3Tag, format::mp4::MP4Tag,
-    format::ogg::OggTag, generic, markers, Autotags, Beatgrid, Markers, Markers2, Overview,
+    beatgrid, beatgrid::Quantization, color::Color, format::flac::FLACTag, format::id3::ID3Tag,
+    format::mp4::MP4Tag, format::ogg::OggTag, generic, markers, seratotag::UnknownTag, Analysis,
+    Autotags, Beatgrid, Markers, Markers2, Offsets, Overview, RelVolAd, VidAssoc,
 };
 use crate::error::Error;
 use std::io;
@@ -15,17 +16,107 @@ use std::io;
 /// Some of the data in Serato's tags is redundant and may contradict each other. This class
 /// implements the same merge strategies for inconsistent data that Serato uses, too.
 pub struct TagContainer {
+    analysis: Option<Analysis>,
     autotags: Option<Autotags>,
     beatgrid: Option<Beatgrid>,
     markers: Option<Markers>,
     markers2: Option<Markers2>,
+    offsets: Option<Offsets>,
     overview: Option<Overview>,
+    relvolad: Option<RelVolAd>,
+    vidassoc: Option<VidAssoc>,
+    unknown_tags: Vec<(TagFormat, UnknownTag)>,
 }
 
+/// A cue moved by [`TagContainer::quantize`].
+#[derive(Debug, Clone)]
+pub struct QuantizedCue {
+    /// The cue with the quantized position.
+    pub cue: generic::Cue,
+    /// The distance (in milliseconds) that the cue was moved.
+    pub offset_millis: i64,
+}
+
+/// A loop moved by [`TagContainer::quantize`].
+#[derive(Debug, Clone)]
+pub struct QuantizedLoop {
+    /// The loop with the quantized start and end positions.
+    pub saved_loop: generic::Loop,
+    /// The distance (in milliseconds) that the start position was moved.
+    pub start_offset_millis: i64,
+    /// The distance (in milliseconds) that the end position was moved.
+    pub end_offset_millis: i64,
+}
+
+/// The cues and loops moved by [`TagContainer::quantize`].
+#[derive(Debug, Clone, Default)]
+pub struct QuantizeReport {
+    /// The moved cues.
+    pub cues: Vec<QuantizedCue>,
+    /// The moved loops.
+    pub loops: Vec<QuantizedLoop>,
+}
+
+/// Returns the grid position closest to `position_millis` (in milliseconds).
+fn quantize_millis(beatgrid: &Beatgrid, position_millis: u32, quantization: Quantization) -> u32 {
+    let position = f64::from(position_millis) / 1000.0;
+    let quantized = (beatgrid.quantize(position, quantization) * 1000.0).round();
+    // Positions before the start of the track can't be stored.
+    quantized.max(0.0).min(f64::from(u32::MAX)) as u32
+}
+
+/// The cue slot, loop slot or track color that a [`MarkersConflict`] refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkersSlot {
+    Cue(u8),
+    Loop(u8),
+    TrackColor,
+}
+
+/// The way that [`Serato Markers_`](Markers) and [`Serato Markers2`](Markers2) contradict each
+/// other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkersConflictKind {
+    /// The slot is set in `Serato Markers2`, but not in `Serato Markers_`.
+    MissingInMarkers,
+    /// The slot is set in `Serato Markers_`, but not in `Serato Markers2`.
+    MissingInMarkers2,
+    /// The (start or end) position differs.
+    Position,
+    /// The color differs.
+    Color,
+    /// The loop lock state differs.
+    Locked,
+    /// The `Serato Markers_` entry is set but lacks a position.
+    InvalidMarker,
+}
+
+/// A conflict between the [`Serato Markers_`](Markers) and [`Serato Markers2`](Markers2) tags,
+/// returned by [`TagContainer::markers_conflicts`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkersConflict {
+    pub slot: MarkersSlot,
+    pub kind: MarkersConflictKind,
+}
+
+/// Selects the tag that [`TagContainer::repair_markers`] regenerates the other tag from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkersRepairPolicy {
+    /// Use the values from `Serato Markers_` (like Serato does) and keep the labels from `Serato
+    /// Markers2`.
+    PreferMarkers,
+    /// Use the values from `Serato Markers2`.
+    PreferMarkers2,
+}
+
+/// Track color that Serato uses for tracks that don't have a color set.
+const DEFAULT_TRACK_COLOR: u32 = 0xFFFFFF;
+
 /// The tag type of the data.
 ///
 /// The format of the Serato tag data differs between tag types.
 /// Therefore it's necessary to tell the parser from what kind of the the data originates from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TagFormat {
     ID3,
     FLAC,
@@ -37,11 +128,53 @@ impl TagContainer {
     /// Create an empty Serato tag container.
     pub fn new() -> Self {
         Self {
+            analysis: None,
             autotags: None,
             beatgrid: None,
             markers: None,
             markers2: None,
+            offsets: None,
             overview: None,
+            relvolad: None,
+            vidassoc: None,
+            unknown_tags: Vec::new(),
+        }
+    }
+
+    /// Parse the [`Serato Analysis`](Analysis) tag.
+    pub fn parse_analysis(&mut self, input: &[u8], tag_format: TagFormat) -> Result<(), Error> {
+        match tag_format {
+            TagFormat::ID3 => {
+                self.analysis = Some(Analysis::parse_id3(input)?);
+            }
+            TagFormat::FLAC => {
+                self.analysis = Some(Analysis::parse_flac(input)?);
+            }
+            TagFormat::MP4 => {
+                self.analysis = Some(Analysis::parse_mp4(input)?);
+            }
+            TagFormat::Ogg => {
+                self.analysis = Some(Analysis::parse_ogg(input)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the [`Serato Analysis`](Analysis) tag.
+    pub fn write_analysis(
+        &self,
+        writer: impl io::Write,
+        tag_format: TagFormat,
+    ) -> Result<usize, Error> {
+        let tag = match &self.analysis {
+            Some(x) => x,
+            None => return Err(Error::NoTagDataAvailable),
+        };
+        match tag_format {
+            TagFormat::ID3 => tag.write_id3(writer),
+            TagFormat::FLAC => tag.write_flac(writer),
+            TagFormat::MP4 => tag.write_mp4(writer),
+            TagFormat::Ogg => tag.write_ogg(writer),
         }
     }
 
@@ -179,7 +312,7 @@ impl TagContainer {
             TagFormat::ID3 => tag.write_id3(writer),
             TagFormat::FLAC => tag.write_flac(writer),
             TagFormat::MP4 => tag.write_mp4(writer),
-            _ => Err(Error::UnsupportedTagFormat),
+            TagFormat::Ogg => tag.write_ogg(writer),
         }
     }
 
@@ -218,6 +351,156 @@ impl TagContainer {
         }
     }
 
+    /// Parse the [`Serato Offsets_`](Offsets) tag.
+    pub fn parse_offsets(&mut self, input: &[u8], tag_format: TagFormat) -> Result<(), Error> {
+        match tag_format {
+            TagFormat::ID3 => {
+                self.offsets = Some(Offsets::parse_id3(input)?);
+            }
+            _ => return Err(Error::UnsupportedTagFormat),
+        }
+        Ok(())
+    }
+
+    /// Write the [`Serato Offsets_`](Offsets) tag.
+    pub fn write_offsets(
+        &self,
+        writer: impl io::Write,
+        tag_format: TagFormat,
+    ) -> Result<usize, Error> {
+        let tag = match &self.offsets {
+            Some(x) => x,
+            None => return Err(Error::NoTagDataAvailable),
+        };
+        match tag_format {
+            TagFormat::ID3 => tag.write_id3(writer),
+            _ => Err(Error::UnsupportedTagFormat),
+        }
+    }
+
+    /// Parse the [`Serato RelVolAd`](RelVolAd) tag.
+    pub fn parse_relvolad(&mut self, input: &[u8], tag_format: TagFormat) -> Result<(), Error> {
+        match tag_format {
+            TagFormat::FLAC => {
+                self.relvolad = Some(RelVolAd::parse_flac(input)?);
+            }
+            TagFormat::MP4 => {
+                self.relvolad = Some(RelVolAd::parse_mp4(input)?);
+            }
+            _ => return Err(Error::UnsupportedTagFormat),
+        }
+        Ok(())
+    }
+
+    /// Write the [`Serato RelVolAd`](RelVolAd) tag.
+    pub fn write_relvolad(
+        &self,
+        writer: impl io::Write,
+        tag_format: TagFormat,
+    ) -> Result<usize, Error> {
+        let tag = match &self.relvolad {
+            Some(x) => x,
+            None => return Err(Error::NoTagDataAvailable),
+        };
+        match tag_format {
+            TagFormat::FLAC => tag.write_flac(writer),
+            TagFormat::MP4 => tag.write_mp4(writer),
+            _ => Err(Error::UnsupportedTagFormat),
+        }
+    }
+
+    /// Parse the [`Serato VidAssoc`](VidAssoc) tag.
+    pub fn parse_vidassoc(&mut self, input: &[u8], tag_format: TagFormat) -> Result<(), Error> {
+        match tag_format {
+            TagFormat::FLAC => {
+                self.vidassoc = Some(VidAssoc::parse_flac(input)?);
+            }
+            TagFormat::MP4 => {
+                self.vidassoc = Some(VidAssoc::parse_mp4(input)?);
+            }
+            _ => return Err(Error::UnsupportedTagFormat),
+        }
+        Ok(())
+    }
+
+    /// Write the [`Serato VidAssoc`](VidAssoc) tag.
+    pub fn write_vidassoc(
+        &self,
+        writer: impl io::Write,
+        tag_format: TagFormat,
+    ) -> Result<usize, Error> {
+        let tag = match &self.vidassoc {
+            Some(x) => x,
+            None => return Err(Error::NoTagDataAvailable),
+        };
+        match tag_format {
+            TagFormat::FLAC => tag.write_flac(writer),
+            TagFormat::MP4 => tag.write_mp4(writer),
+            _ => Err(Error::UnsupportedTagFormat),
+        }
+    }
+
+    /// Store the data of a tag that is not supported by the container (e.g. `Serato Offsets_`).
+    ///
+    /// The data is kept as-is, so that it can be written back unchanged to files with the same tag
+    /// format. An existing tag with the same name and tag format is replaced.
+    ///
+    /// **Note:** Writing tags to MP3, AIFF and WAV files is not supported yet, so unknown tags read
+    /// from these files are only available through [`TagContainer::unknown_tag`] and are not
+    /// written back.
+    pub fn set_unknown_tag(&mut self, name: &str, data: Vec<u8>, tag_format: TagFormat) {
+        let tag = UnknownTag {
+            name: name.to_owned(),
+            data,
+        };
+        match self.unknown_tag_position(name, tag_format) {
+            Some(index) => self.unknown_tags[index].1 = tag,
+            None => self.unknown_tags.push((tag_format, tag)),
+        }
+    }
+
+    /// Returns the data of an unsupported tag, if present.
+    pub fn unknown_tag(&self, name: &str, tag_format: TagFormat) -> Option<&[u8]> {
+        let index = self.unknown_tag_position(name, tag_format)?;
+        Some(&self.unknown_tags[index].1.data)
+    }
+
+    /// Removes an unsupported tag and returns it.
+    pub fn remove_unknown_tag(&mut self, name: &str, tag_format: TagFormat) -> Option<UnknownTag> {
+        let index = self.unknown_tag_position(name, tag_format)?;
+        Some(self.unknown_tags.remove(index).1)
+    }
+
+    /// Returns all unsupported tags for the given tag format, in the order they were added.
+    pub fn unknown_tags(&self, tag_format: TagFormat) -> impl Iterator<Item = &UnknownTag> {
+        self.unknown_tags
+            .iter()
+            .filter(move |(format, _)| *format == tag_format)
+            .map(|(_, tag)| tag)
+    }
+
+    /// Returns the index of an unsupported tag.
+    ///
+    /// Names of FLAC and Ogg comments are compared case-insensitively.
+    fn unknown_tag_position(&self, name: &str, tag_format: TagFormat) -> Option<usize> {
+        self.unknown_tags.iter().position(|(format, tag)| {
+            *format == tag_format
+                && match tag_format {
+                    TagFormat::FLAC | TagFormat::Ogg => tag.name.eq_ignore_ascii_case(name),
+                    TagFormat::ID3 | TagFormat::MP4 => tag.name == name,
+                }
+        })
+    }
+
+    /// Returns the analysis version from the [`Serato Analysis`](Analysis) tag.
+    pub fn analysis_version(&self) -> Option<&generic::Version> {
+        if let Some(tag) = &self.analysis {
+            return Some(&tag.version);
+        }
+
+        None
+    }
+
     /// Returns the [`auto_gain`](Autotags::auto_gain) value from the [`Serato Autotags`](Autotags) tag.
     pub fn auto_gain(&self) -> Option<f64> {
         if let Some(tag) = &self.autotags {
@@ -282,10 +565,9 @@ impl TagContainer {
                         continue;
                     }
                     markers::MarkerType::Cue => {
-                        if marker.start_position_millis == None {
+                        if marker.start_position_millis.is_none() {
                             // This shouldn't be possible if the `Serato Markers_` data is valid.
-                            // Ideally, this should be checked during the parsing state.
-                            // FIXME: Throw error here?
+                            // Such entries are reported by `markers_conflicts()`.
                             map.remove(&index);
                             continue;
                         }
@@ -324,7 +606,9 @@ impl TagContainer {
     ///
     /// This retrieves the `Serato Markers2` loops first, then overwrite the values with those from
     /// `Serato Markers_`. This is what Serato does too (i.e. if `Serato Markers_` and `Serato
-    /// Markers2` contradict each other, Serato will use the values from `Serato Markers_`).
+    /// Markers2` contradict each other, Serato will use the values from `Serato Markers_`). The
+    /// loop color is taken from `Serato Markers2`, since `Serato Markers_` always stores
+    /// [`#27AAE1`](markers::LOOP_COLOR).
     pub fn loops(&self) -> Vec<generic::Loop> {
         let mut map = std::collections::BTreeMap::new();
 
@@ -340,12 +624,11 @@ impl TagContainer {
             for (index, marker) in m.loops() {
                 if marker.marker_type != markers::MarkerType::Loop {
                     // This can only happen is `Markers::cues()` returns non-cue markers, which
-                    // would be a bug.
-                    // FIXME: Throw error here?
+                    // would be a bug. Such entries are reported by `markers_conflicts()`.
                     continue;
                 }
 
-                if marker.start_position_millis == None || marker.end_position_millis == None {
+                if marker.start_position_millis.is_none() || marker.end_position_millis.is_none() {
                     // This may happen even for valid data, because unset loops lack the start/end
                     // position.
                     map.remove(&index);
@@ -356,12 +639,13 @@ impl TagContainer {
                 let end_position_millis = marker.end_position_millis.unwrap();
 
                 // If the loop is set in both `Serato Markers2` and `Serato Markers_`, use
-                // the version from `Serato Markers_`, but keep the label from `Serato
-                // Markers2` because the `Serato Markers_` tag doesn't contain labels.
+                // the version from `Serato Markers_`, but keep the color and label from `Serato
+                // Markers2` because the `Serato Markers_` tag doesn't contain labels and always
+                // stores the default loop color.
                 let markers2_loop = map.remove(&index);
-                let label = match markers2_loop {
-                    Some(c) => c.label,
-                    None => String::new(),
+                let (color, label) = match markers2_loop {
+                    Some(c) => (c.color, c.label),
+                    None => (marker.color, String::new()),
                 };
 
                 map.insert(
@@ -370,7 +654,7 @@ impl TagContainer {
                         index,
                         start_position_millis,
                         end_position_millis,
-                        color: marker.color,
+                        color,
                         label,
                         is_locked: marker.is_locked,
                     },
@@ -412,6 +696,309 @@ impl TagContainer {
         track_color
     }
 
+    /// Returns the [`Serato Markers_`](Markers) and [`Serato Markers2`](Markers2) tags for
+    /// modification.
+    ///
+    /// Missing tags are created from the current (merged) cues, loops, track color and BPM lock
+    /// status, so that both tags are consistent afterwards.
+    fn markers_mut(&mut self) -> (&mut Markers, &mut Markers2) {
+        if self.markers.is_none() || self.markers2.is_none() {
+            let track_color = self
+                .track_color()
+                .unwrap_or_else(|| Color::from(DEFAULT_TRACK_COLOR));
+            let bpm_locked = self.bpm_locked().unwrap_or(false);
+            let cues = self.cues();
+            let loops = self.loops();
+
+            if self.markers.is_none() {
+                let mut tag = Markers::new(track_color);
+                for cue in &cues {
+                    tag.set_cue(cue);
+                }
+                for saved_loop in &loops {
+                    tag.set_loop(saved_loop);
+                }
+                self.markers = Some(tag);
+            }
+
+            if self.markers2.is_none() {
+                let mut tag = Markers2::new(track_color);
+                tag.set_bpm_locked(bpm_locked);
+                for cue in cues {
+                    tag.set_cue(cue);
+                }
+                for saved_loop in loops {
+                    tag.set_loop(saved_loop);
+                }
+                self.markers2 = Some(tag);
+            }
+        }
+
+        match (&mut self.markers, &mut self.markers2) {
+            (Some(markers), Some(markers2)) => (markers, markers2),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Sets a cue in the [`Serato Markers_`](Markers) and [`Serato Markers2`](Markers2) tags.
+    ///
+    /// An existing cue with the same index is replaced. Since `Serato Markers_` only has 5 cue
+    /// slots, cues with a higher index are only stored in `Serato Markers2`.
+    pub fn set_cue(&mut self, cue: generic::Cue) {
+        let (markers, markers2) = self.markers_mut();
+        markers.set_cue(&cue);
+        markers2.set_cue(cue);
+    }
+
+    /// Removes a cue from the [`Serato Markers_`](Markers) and [`Serato Markers2`](Markers2) tags.
+    pub fn remove_cue(&mut self, index: u8) {
+        if let Some(m) = &mut self.markers {
+            m.remove_cue(index);
+        }
+
+        if let Some(m) = &mut self.markers2 {
+            m.remove_cue(index);
+        }
+    }
+
+    /// Sets a loop in the [`Serato Markers_`](Markers) and [`Serato Markers2`](Markers2) tags.
+    ///
+    /// An existing loop with the same index is replaced. Since `Serato Markers_` only has 9 loop
+    /// slots, loops with a higher index are only stored in `Serato Markers2`.
+    ///
+    /// **Note:** `Serato Markers_` always stores the color [`#27AAE1`](markers::LOOP_COLOR) for
+    /// loops, so other colors are only stored in `Serato Markers2`.
+    pub fn set_loop(&mut self, saved_loop: generic::Loop) {
+        let (markers, markers2) = self.markers_mut();
+        markers.set_loop(&saved_loop);
+        markers2.set_loop(saved_loop);
+    }
+
+    /// Removes a loop from the [`Serato Markers_`](Markers) and [`Serato Markers2`](Markers2)
+    /// tags.
+    pub fn remove_loop(&mut self, index: u8) {
+        if let Some(m) = &mut self.markers {
+            m.remove_loop(index);
+        }
+
+        if let Some(m) = &mut self.markers2 {
+            m.remove_loop(index);
+        }
+    }
+
+    /// Sets the track color in the [`Serato Markers_`](Markers) and [`Serato
+    /// Markers2`](Markers2) tags.
+    pub fn set_track_color(&mut self, color: Color) {
+        let (markers, markers2) = self.markers_mut();
+        markers.set_track_color(color);
+        markers2.set_track_color(color);
+    }
+
+    /// Sets the BPM lock status in the [`Serato Markers2`](Markers2) tag.
+    ///
+    /// Like the other setters, this creates the [`Serato Markers_`](Markers) tag if it doesn't
+    /// exist yet.
+    pub fn set_bpm_locked(&mut self, is_locked: bool) {
+        let (_, markers2) = self.markers_mut();
+        markers2.set_bpm_locked(is_locked);
+    }
+
+    /// Returns all conflicts between the [`Serato Markers_`](Markers) and [`Serato
+    /// Markers2`](Markers2) tags.
+    ///
+    /// Only the slots that exist in `Serato Markers_` (i.e. the first 5 cues and 9 loops) are
+    /// compared. Loop colors are ignored, because Serato always stores
+    /// [`#27AAE1`](markers::LOOP_COLOR) in `Serato Markers_`. If one of the tags is missing, there
+    /// are no conflicts.
+    pub fn markers_conflicts(&self) -> Vec<MarkersConflict> {
+        let (markers, markers2) = match (&self.markers, &self.markers2) {
+            (Some(markers), Some(markers2)) => (markers, markers2),
+            _ => return vec![],
+        };
+
+        let mut conflicts = Vec::new();
+        let mut conflict = |slot, kind| conflicts.push(MarkersConflict { slot, kind });
+
+        let cues2 = markers2.cues();
+        for (index, marker) in markers.cues() {
+            let slot = MarkersSlot::Cue(index);
+            let cue2 = cues2.iter().find(|cue| cue.index == index);
+            match (&marker.marker_type, marker.start_position_millis, cue2) {
+                (&markers::MarkerType::Invalid, _, None) => {}
+                (&markers::MarkerType::Invalid, _, Some(_)) => {
+                    conflict(slot, MarkersConflictKind::MissingInMarkers)
+                }
+                (_, None, _) => conflict(slot, MarkersConflictKind::InvalidMarker),
+                (_, Some(_), None) => conflict(slot, MarkersConflictKind::MissingInMarkers2),
+                (_, Some(position_millis), Some(cue)) => {
+                    if position_millis != cue.position_millis {
+                        conflict(slot, MarkersConflictKind::Position);
+                    }
+                    if marker.color != cue.color {
+                        conflict(slot, MarkersConflictKind::Color);
+                    }
+                }
+            }
+        }
+
+        let loops2 = markers2.loops();
+        for (index, marker) in markers.loops() {
+            let slot = MarkersSlot::Loop(index);
+            if marker.marker_type != markers::MarkerType::Loop {
+                conflict(slot, MarkersConflictKind::InvalidMarker);
+                continue;
+            }
+            let loop2 = loops2.iter().find(|saved_loop| saved_loop.index == index);
+            match (
+                marker.start_position_millis,
+                marker.end_position_millis,
+                loop2,
+            ) {
+                (None, None, None) => {}
+                (None, None, Some(_)) => conflict(slot, MarkersConflictKind::MissingInMarkers),
+                (Some(_), None, _) | (None, Some(_), _) => {
+                    conflict(slot, MarkersConflictKind::InvalidMarker)
+                }
+                (Some(_), Some(_), None) => conflict(slot, MarkersConflictKind::MissingInMarkers2),
+                (Some(start_position_millis), Some(end_position_millis), Some(saved_loop)) => {
+                    if start_position_millis != saved_loop.start_position_millis
+                        || end_position_millis != saved_loop.end_position_millis
+                    {
+                        conflict(slot, MarkersConflictKind::Position);
+                    }
+                    if marker.is_locked != saved_loop.is_locked {
+                        conflict(slot, MarkersConflictKind::Locked);
+                    }
+                }
+            }
+        }
+
+        match markers2.track_color() {
+            None => conflict(
+                MarkersSlot::TrackColor,
+                MarkersConflictKind::MissingInMarkers2,
+            ),
+            Some(color) if color != markers.track_color() => {
+                conflict(MarkersSlot::TrackColor, MarkersConflictKind::Color)
+            }
+            Some(_) => {}
+        }
+
+        conflicts
+    }
+
+    /// Makes the [`Serato Markers_`](Markers) and [`Serato Markers2`](Markers2) tags consistent by
+    /// regenerating one from the other.
+    ///
+    /// Cues and loops that don't fit into the `Serato Markers_` slots as well as the BPM lock
+    /// status and flips are kept in `Serato Markers2`. Missing tags are created.
+    pub fn repair_markers(&mut self, policy: MarkersRepairPolicy) {
+        let (cues, loops, track_color) = match (policy, &self.markers2) {
+            (MarkersRepairPolicy::PreferMarkers2, Some(m)) => {
+                (m.cues(), m.loops(), m.track_color())
+            }
+            _ => (self.cues(), self.loops(), self.track_color()),
+        };
+        let track_color = track_color.unwrap_or_else(|| Color::from(DEFAULT_TRACK_COLOR));
+
+        let (markers, markers2) = self.markers_mut();
+        markers.set_track_color(track_color);
+        markers2.set_track_color(track_color);
+
+        for index in 0..markers::CUE_SLOTS as u8 {
+            match cues.iter().find(|cue| cue.index == index) {
+                Some(cue) => {
+                    markers.set_cue(cue);
+                    markers2.set_cue(cue.clone());
+                }
+                None => {
+                    markers.remove_cue(index);
+                    markers2.remove_cue(index);
+                }
+            }
+        }
+
+        for index in 0..markers::LOOP_SLOTS as u8 {
+            match loops.iter().find(|saved_loop| saved_loop.index == index) {
+                Some(saved_loop) => {
+                    markers.set_loop(saved_loop);
+                    markers2.set_loop(saved_loop.clone());
+                }
+                None => {
+                    markers.remove_loop(index);
+                    markers2.remove_loop(index);
+                }
+            }
+        }
+    }
+
+    /// Returns the cues and loops with their positions snapped to the [`Serato
+    /// BeatGrid`](Beatgrid), without modifying the tags.
+    ///
+    /// Only cues and loops that would be moved are included. If snapping a loop would collapse it,
+    /// its original length is kept. Returns [`Error::NoTagDataAvailable`] if there is no beatgrid
+    /// and [`Error::InvalidBeatgrid`] if the beatgrid is not [valid](Beatgrid::validate).
+    pub fn quantized(&self, quantization: Quantization) -> Result<QuantizeReport, Error> {
+        let beatgrid = match &self.beatgrid {
+            Some(x) => x,
+            None => return Err(Error::NoTagDataAvailable),
+        };
+        beatgrid.validate()?;
+
+        let mut report = QuantizeReport::default();
+        for mut cue in self.cues() {
+            let position_millis = quantize_millis(beatgrid, cue.position_millis, quantization);
+            let offset_millis = i64::from(position_millis) - i64::from(cue.position_millis);
+            if offset_millis != 0 {
+                cue.position_millis = position_millis;
+                report.cues.push(QuantizedCue { cue, offset_millis });
+            }
+        }
+
+        for mut saved_loop in self.loops() {
+            let start_position_millis =
+                quantize_millis(beatgrid, saved_loop.start_position_millis, quantization);
+            let mut end_position_millis =
+                quantize_millis(beatgrid, saved_loop.end_position_millis, quantization);
+            if end_position_millis <= start_position_millis {
+                let length_millis = saved_loop
+                    .end_position_millis
+                    .saturating_sub(saved_loop.start_position_millis);
+                end_position_millis = start_position_millis.saturating_add(length_millis);
+            }
+            let start_offset_millis =
+                i64::from(start_position_millis) - i64::from(saved_loop.start_position_millis);
+            let end_offset_millis =
+                i64::from(end_position_millis) - i64::from(saved_loop.end_position_millis);
+            if start_offset_millis != 0 || end_offset_millis != 0 {
+                saved_loop.start_position_millis = start_position_millis;
+                saved_loop.end_position_millis = end_position_millis;
+                report.loops.push(QuantizedLoop {
+                    saved_loop,
+                    start_offset_millis,
+                    end_offset_millis,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Snaps all cues and loops to the [`Serato BeatGrid`](Beatgrid) and stores the new
+    /// positions in the [`Serato Markers_`](Markers) and [`Serato Markers2`](Markers2) tags.
+    ///
+    /// Returns the moved cues and loops (see [`TagContainer::quantized`]).
+    pub fn quantize(&mut self, quantization: Quantization) -> Result<QuantizeReport, Error> {
+        let report = self.quantized(quantization)?;
+        for quantized in &report.cues {
+            self.set_cue(quantized.cue.clone());
+        }
+        for quantized in &report.loops {
+            self.set_loop(quantized.saved_loop.clone());
+        }
+        Ok(report)
+    }
+
     /// Returns the waveform overview data color from the [`Serato Overview`](Overview) tag.
     pub fn overview(&self) -> Option<&Vec<Vec<u8>>> {
         if let Some(tag) = &self.overview {
@@ -420,6 +1007,33 @@ impl TagContainer {
 
         None
     }
+
+    /// Returns the [`Serato Offsets_`](Offsets) tag.
+    pub fn offsets(&self) -> Option<&Offsets> {
+        if let Some(tag) = &self.offsets {
+            return Some(tag);
+        }
+
+        None
+    }
+
+    /// Returns the [`Serato RelVolAd`](RelVolAd) tag.
+    pub fn relvolad(&self) -> Option<&RelVolAd> {
+        if let Some(tag) = &self.relvolad {
+            return Some(tag);
+        }
+
+        None
+    }
+
+    /// Returns the [`Serato VidAssoc`](VidAssoc) tag.
+    pub fn vidassoc(&self) -> Option<&VidAssoc> {
+        if let Some(tag) = &self.vidassoc {
+            return Some(tag);
+        }
+
+        None
+    }
 }
 
 impl Default for TagContainer {
@@ -0,0 +1,247 @@
+//! Reading and writing Serato tags in FLAC files.
+//!
+//! Serato stores its tags as base64-encoded `SERATO_*` comments in the `VORBIS_COMMENT` metadata
+//! block. When writing, the metadata blocks are rewritten in place by taking space from (or giving
+//! space back to) the `PADDING` block. If the padding is too small, the whole file is rewritten
+//! to a temporary file that replaces the original.
+
+use super::replace_file;
+use super::vorbis::VorbisComment;
+use crate::error::Error;
+use crate::tag::format::flac::FLACTag;
+use crate::tag::{
+    Analysis, Autotags, Beatgrid, Markers2, Overview, RelVolAd, TagContainer, TagFormat, VidAssoc,
+};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Magic bytes at the start of each FLAC file.
+const FLAC_MARKER: &[u8; 4] = b"fLaC";
+/// Block type of the `STREAMINFO` metadata block.
+const BLOCK_STREAMINFO: u8 = 0;
+/// Block type of the `PADDING` metadata block.
+const BLOCK_PADDING: u8 = 1;
+/// Block type of the `VORBIS_COMMENT` metadata block.
+const BLOCK_VORBIS_COMMENT: u8 = 4;
+/// Flag in the block header that marks the last metadata block.
+const BLOCK_LAST_FLAG: u8 = 0x80;
+/// Maximum length of a metadata block (24 bits).
+const BLOCK_MAX_LENGTH: usize = 0xFF_FFFF;
+/// Length of the padding that is added if the audio frames need to be moved.
+const DEFAULT_PADDING_LENGTH: usize = 4096;
+/// Prefix of the comment names used by Serato.
+const COMMENT_PREFIX: &str = "SERATO_";
+/// Vendor string used if a new `VORBIS_COMMENT` block needs to be created.
+const VENDOR_STRING: &str = concat!("triseratops ", env!("CARGO_PKG_VERSION"));
+
+/// A FLAC metadata block.
+#[derive(Debug, Clone)]
+struct MetadataBlock {
+    block_type: u8,
+    data: Vec<u8>,
+}
+
+/// Read all metadata blocks from the start of a FLAC stream.
+fn read_metadata_blocks(mut reader: impl Read) -> Result<Vec<MetadataBlock>, Error> {
+    let mut marker = [0; 4];
+    reader.read_exact(&mut marker)?;
+    if &marker != FLAC_MARKER {
+        return Err(Error::IOError(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Not a FLAC file",
+        )));
+    }
+
+    let mut blocks = Vec::new();
+    loop {
+        let mut header = [0; 4];
+        reader.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let mut data = vec![0; length];
+        reader.read_exact(&mut data)?;
+        blocks.push(MetadataBlock {
+            block_type: header[0] & !BLOCK_LAST_FLAG,
+            data,
+        });
+        if header[0] & BLOCK_LAST_FLAG != 0 {
+            return Ok(blocks);
+        }
+    }
+}
+
+/// Returns the number of bytes from the start of the file to the first audio frame.
+fn metadata_length(blocks: &[MetadataBlock]) -> usize {
+    FLAC_MARKER.len()
+        + blocks
+            .iter()
+            .map(|block| 4 + block.data.len())
+            .sum::<usize>()
+}
+
+/// Write the FLAC marker and all metadata blocks.
+fn write_metadata_blocks(mut writer: impl Write, blocks: &[MetadataBlock]) -> Result<usize, Error> {
+    let mut bytes_written = writer.write(FLAC_MARKER)?;
+    for (i, block) in blocks.iter().enumerate() {
+        if block.data.len() > BLOCK_MAX_LENGTH {
+            return Err(Error::IOError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Metadata block too long",
+            )));
+        }
+        let mut header = (block.data.len() as u32).to_be_bytes();
+        header[0] = block.block_type;
+        if i + 1 == blocks.len() {
+            header[0] |= BLOCK_LAST_FLAG;
+        }
+        bytes_written += writer.write(&header)?;
+        bytes_written += writer.write(&block.data)?;
+    }
+    Ok(bytes_written)
+}
+
+impl TagContainer {
+    /// Parse all Serato comments from a FLAC `VORBIS_COMMENT` block.
+    ///
+    /// Serato comments that do not belong to a tag supported by the container are stored as
+    /// unknown tags, all other comments are ignored.
+    fn parse_flac_comment(&mut self, comment: &VorbisComment) -> Result<(), Error> {
+        for (name, value) in comment.iter() {
+            let name = String::from_utf8_lossy(name);
+            match name.to_ascii_uppercase().as_str() {
+                Analysis::FLAC_COMMENT => self.parse_analysis(value, TagFormat::FLAC)?,
+                Autotags::FLAC_COMMENT => self.parse_autotags(value, TagFormat::FLAC)?,
+                Beatgrid::FLAC_COMMENT => self.parse_beatgrid(value, TagFormat::FLAC)?,
+                Markers2::FLAC_COMMENT => self.parse_markers2(value, TagFormat::FLAC)?,
+                Overview::FLAC_COMMENT => self.parse_overview(value, TagFormat::FLAC)?,
+                RelVolAd::FLAC_COMMENT => self.parse_relvolad(value, TagFormat::FLAC)?,
+                VidAssoc::FLAC_COMMENT => self.parse_vidassoc(value, TagFormat::FLAC)?,
+                n if n.starts_with(COMMENT_PREFIX) => {
+                    self.set_unknown_tag(&name, value.to_vec(), TagFormat::FLAC)
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the Serato comments in a FLAC `VORBIS_COMMENT` block.
+    ///
+    /// Comments for tags that are not present in the container are left untouched. Unknown tags
+    /// are written back unchanged.
+    fn update_flac_comment(&self, comment: &mut VorbisComment) -> Result<(), Error> {
+        comment.set_tag(Analysis::FLAC_COMMENT, |w| {
+            self.write_analysis(w, TagFormat::FLAC)
+        })?;
+        comment.set_tag(Autotags::FLAC_COMMENT, |w| {
+            self.write_autotags(w, TagFormat::FLAC)
+        })?;
+        comment.set_tag(Beatgrid::FLAC_COMMENT, |w| {
+            self.write_beatgrid(w, TagFormat::FLAC)
+        })?;
+        comment.set_tag(Markers2::FLAC_COMMENT, |w| {
+            self.write_markers2(w, TagFormat::FLAC)
+        })?;
+        comment.set_tag(Overview::FLAC_COMMENT, |w| {
+            self.write_overview(w, TagFormat::FLAC)
+        })?;
+        comment.set_tag(RelVolAd::FLAC_COMMENT, |w| {
+            self.write_relvolad(w, TagFormat::FLAC)
+        })?;
+        comment.set_tag(VidAssoc::FLAC_COMMENT, |w| {
+            self.write_vidassoc(w, TagFormat::FLAC)
+        })?;
+        for tag in self.unknown_tags(TagFormat::FLAC) {
+            comment.set(&tag.name, &tag.data);
+        }
+        Ok(())
+    }
+}
+
+/// Read the Serato tags from a FLAC stream.
+pub fn read_flac_from(reader: impl Read) -> Result<TagContainer, Error> {
+    let mut container = TagContainer::new();
+    for block in read_metadata_blocks(reader)? {
+        if block.block_type == BLOCK_VORBIS_COMMENT {
+            let (comment, _) = VorbisComment::parse(&block.data)?;
+            container.parse_flac_comment(&comment)?;
+        }
+    }
+    Ok(container)
+}
+
+/// Read the Serato tags from a FLAC file.
+pub fn read_flac(path: &Path) -> Result<TagContainer, Error> {
+    read_flac_from(BufReader::new(File::open(path)?))
+}
+
+/// Write the Serato tags from `container` to a FLAC file.
+///
+/// The file is updated in place. If the existing padding is not large enough to hold the new
+/// metadata, the file is rewritten with new padding through a temporary file, so that the original
+/// is left intact if writing fails.
+pub fn write_flac(path: &Path, container: &TagContainer) -> Result<(), Error> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let blocks = read_metadata_blocks(BufReader::new(&mut file))?;
+    let old_length = metadata_length(&blocks);
+
+    let mut blocks: Vec<MetadataBlock> = blocks
+        .into_iter()
+        .filter(|block| block.block_type != BLOCK_PADDING)
+        .collect();
+    let index = match blocks
+        .iter()
+        .position(|block| block.block_type == BLOCK_VORBIS_COMMENT)
+    {
+        Some(index) => index,
+        None => {
+            let mut data = Vec::new();
+            VorbisComment::new(VENDOR_STRING).write(&mut data)?;
+            let index = blocks
+                .iter()
+                .position(|block| block.block_type != BLOCK_STREAMINFO)
+                .unwrap_or(blocks.len());
+            blocks.insert(
+                index,
+                MetadataBlock {
+                    block_type: BLOCK_VORBIS_COMMENT,
+                    data,
+                },
+            );
+            index
+        }
+    };
+    let (mut comment, rest) = VorbisComment::parse(&blocks[index].data)?;
+    let rest = rest.to_vec();
+    container.update_flac_comment(&mut comment)?;
+    let mut data = Vec::new();
+    comment.write(&mut data)?;
+    data.extend(rest);
+    blocks[index].data = data;
+
+    let new_length = metadata_length(&blocks);
+    if new_length == old_length || new_length + 4 <= old_length {
+        if new_length < old_length {
+            blocks.push(MetadataBlock {
+                block_type: BLOCK_PADDING,
+                data: vec![0; old_length - new_length - 4],
+            });
+        }
+        file.seek(SeekFrom::Start(0))?;
+        write_metadata_blocks(&mut file, &blocks)?;
+        file.flush()?;
+        Ok(())
+    } else {
+        blocks.push(MetadataBlock {
+            block_type: BLOCK_PADDING,
+            data: vec![0; DEFAULT_PADDING_LENGTH],
+        });
+        let mut data = Vec::new();
+        write_metadata_blocks(&mut data, &blocks)?;
+        file.seek(SeekFrom::Start(old_length as u64))?;
+        file.read_to_end(&mut data)?;
+        drop(file);
+        replace_file(path, &data)
+    }
+}
@@ -0,0 +1,73 @@
+//! Reading Serato `GEOB` frames from ID3v2 tags (used by MP3, AIFF and WAV files).
+//!
+//! Serato stores each tag in a separate `GEOB` (General Encapsulated Object) frame, using the tag
+//! name (e.g. `Serato Markers2`) as frame description.
+
+use crate::error::Error;
+use crate::tag::format::id3::ID3Tag;
+use crate::tag::{
+    Analysis, Autotags, Beatgrid, Markers, Markers2, Offsets, Overview, TagContainer, TagFormat,
+};
+use std::path::Path;
+
+/// Prefix of the `GEOB` frame descriptions used by Serato.
+const TAG_PREFIX: &str = "Serato ";
+
+/// Parse the Serato tags from the result of reading an ID3 tag.
+///
+/// Files without an ID3 tag are not treated as error, but result in an empty container.
+fn parse_tag(result: ::id3::Result<::id3::Tag>) -> Result<TagContainer, Error> {
+    let mut container = TagContainer::new();
+    match result {
+        Ok(tag) => container.parse_id3_tag(&tag)?,
+        Err(::id3::Error {
+            kind: ::id3::ErrorKind::NoTag,
+            ..
+        }) => (),
+        Err(err) => return Err(err.into()),
+    }
+    Ok(container)
+}
+
+/// Read the Serato tags from the ID3v2 tag of an MP3 file.
+pub fn read_mp3(path: &Path) -> Result<TagContainer, Error> {
+    parse_tag(::id3::Tag::read_from_path(path))
+}
+
+/// Read the Serato tags from the `ID3 ` chunk of an AIFF file.
+pub fn read_aiff(path: &Path) -> Result<TagContainer, Error> {
+    parse_tag(::id3::Tag::read_from_aiff(path))
+}
+
+/// Read the Serato tags from the `id3 ` chunk of a WAV file.
+pub fn read_wav(path: &Path) -> Result<TagContainer, Error> {
+    parse_tag(::id3::Tag::read_from_wav(path))
+}
+
+impl TagContainer {
+    /// Parse all Serato `GEOB` frames in an ID3v2 tag.
+    ///
+    /// Serato frames that do not belong to a tag supported by the container are stored as unknown
+    /// tags, all other frames are ignored.
+    ///
+    /// **Note:** There is no ID3 write path yet, so the unknown tags are not written back to the
+    /// file. They can only be accessed using [`TagContainer::unknown_tag`].
+    pub fn parse_id3_tag(&mut self, tag: &::id3::Tag) -> Result<(), Error> {
+        for geob in tag.encapsulated_objects() {
+            match geob.description.as_str() {
+                Analysis::ID3_TAG => self.parse_analysis(&geob.data, TagFormat::ID3)?,
+                Autotags::ID3_TAG => self.parse_autotags(&geob.data, TagFormat::ID3)?,
+                Beatgrid::ID3_TAG => self.parse_beatgrid(&geob.data, TagFormat::ID3)?,
+                Markers::ID3_TAG => self.parse_markers(&geob.data, TagFormat::ID3)?,
+                Markers2::ID3_TAG => self.parse_markers2(&geob.data, TagFormat::ID3)?,
+                Offsets::ID3_TAG => self.parse_offsets(&geob.data, TagFormat::ID3)?,
+                Overview::ID3_TAG => self.parse_overview(&geob.data, TagFormat::ID3)?,
+                name if name.starts_with(TAG_PREFIX) => {
+                    self.set_unknown_tag(name, geob.data.clone(), TagFormat::ID3)
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+//! Reading Serato tags directly from media files.
+//!
+//! The file type is determined by the file extension. Support for the individual file types has to
+//! be enabled using the corresponding crate feature:
+//!
+//! | File type        | Extensions             | Feature
+//! | ---------------- | ---------------------- | -------
+//! | MP3              | `.mp3`                 | `id3`
+//! | AIFF             | `.aif`, `.aiff`        | `id3`
+//! | WAV              | `.wav`                 | `id3`
+//! | FLAC             | `.flac`                | *none*
+//! | MP4              | `.mp4`, `.m4a`         | *none*
+//! | Ogg Vorbis/Opus  | `.ogg`, `.oga`, `.opus`| *none*
+pub mod flac;
+#[cfg(feature = "id3")]
+pub mod id3;
+pub mod mp4;
+pub mod ogg;
+mod vorbis;
+
+use super::TagContainer;
+use crate::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+/// Returns the lowercase file extension of `path`.
+fn file_extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/// Replace the content of the file at `path` with `data`.
+///
+/// The data is written to a temporary file in the same directory first, which is then renamed over
+/// the original file, so that the original is left intact if writing fails. If the file does not
+/// exist yet, it is created.
+pub(crate) fn replace_file(path: &Path, data: &[u8]) -> Result<(), Error> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path is not a file"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| -> Result<(), Error> {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        match fs::metadata(path) {
+            Ok(metadata) => fs::set_permissions(&temp_path, metadata.permissions())?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err.into()),
+        }
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+impl TagContainer {
+    /// Read all Serato tags from the media file at `path`.
+    ///
+    /// Returns [`Error::UnsupportedFileType`] if the file type is unknown or support for it has
+    /// not been enabled.
+    pub fn read_from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        match file_extension(path).as_deref() {
+            #[cfg(feature = "id3")]
+            Some("mp3") => id3::read_mp3(path),
+            #[cfg(feature = "id3")]
+            Some("aif") | Some("aiff") => id3::read_aiff(path),
+            #[cfg(feature = "id3")]
+            Some("wav") => id3::read_wav(path),
+            Some("flac") => flac::read_flac(path),
+            Some("mp4") | Some("m4a") => mp4::read_mp4(path),
+            Some("ogg") | Some("oga") | Some("opus") => ogg::read_ogg(path),
+            _ => Err(Error::UnsupportedFileType),
+        }
+    }
+
+    /// Write all Serato tags to the media file at `path`.
+    ///
+    /// Tags that are not present in the container are left untouched in the file.
+    ///
+    /// Returns [`Error::UnsupportedFileType`] if the file type is unknown or writing it is not
+    /// supported.
+    pub fn write_to_path(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        match file_extension(path).as_deref() {
+            Some("flac") => flac::write_flac(path, self),
+            Some("mp4") | Some("m4a") => mp4::write_mp4(path, self),
+            Some("ogg") | Some("oga") | Some("opus") => ogg::write_ogg(path, self),
+            _ => Err(Error::UnsupportedFileType),
+        }
+    }
+}
@@ -0,0 +1,561 @@
+//! Reading and writing Serato tags in MP4/M4A files.
+//!
+//! Serato stores its tags as base64-encoded freeform (`----`) atoms with the mean `com.serato.dj`
+//! in the `moov/udta/meta/ilst` atom. When the size of the `moov` atom changes on write, a directly
+//! following `free` atom is used to absorb the difference. If that is not possible, the file is
+//! rewritten to a temporary file that replaces the original, and the chunk offsets in the
+//! `stco`/`co64` atoms are updated accordingly.
+extern crate nom;
+
+use super::replace_file;
+use crate::error::Error;
+use crate::tag::format::mp4::{MP4Tag, SERATO_MEAN};
+use crate::tag::{
+    Analysis, Autotags, Beatgrid, Markers, Markers2, Overview, RelVolAd, TagContainer, TagFormat,
+    VidAssoc,
+};
+use crate::util::Res;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Atoms that contain other atoms (and that need to be traversed to find the tags or chunk
+/// offsets).
+const CONTAINER_ATOMS: [&[u8; 4]; 9] = [
+    b"moov", b"trak", b"mdia", b"minf", b"stbl", b"udta", b"meta", b"ilst", b"----",
+];
+/// Data type of `data` atoms that contain UTF-8 text.
+const DATA_TYPE_UTF8: u32 = 1;
+
+/// An MP4 atom (also called "box").
+#[derive(Debug, Clone, PartialEq)]
+struct Atom {
+    kind: [u8; 4],
+    content: AtomContent,
+    /// Indicates whether the atom length is stored as 64-bit "largesize".
+    large_size: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum AtomContent {
+    /// Raw atom data.
+    Data(Vec<u8>),
+    /// Child atoms, optionally preceded by a header (e.g. version and flags of `meta` atoms) and
+    /// followed by a trailer (e.g. the 4-byte zero terminator of QuickTime `udta` atoms).
+    Children {
+        header: Vec<u8>,
+        children: Vec<Atom>,
+        trailer: Vec<u8>,
+    },
+}
+
+/// Header of a top-level atom in a file.
+#[derive(Debug)]
+struct AtomHeader {
+    kind: [u8; 4],
+    offset: u64,
+    length: u64,
+}
+
+fn take_atom_kind(input: &[u8]) -> Res<&[u8], [u8; 4]> {
+    let (input, kind) = nom::bytes::complete::take(4usize)(input)?;
+    Ok((input, [kind[0], kind[1], kind[2], kind[3]]))
+}
+
+fn take_atom(input: &[u8]) -> Res<&[u8], Atom> {
+    let (input, length) = nom::number::complete::be_u32(input)?;
+    let (input, kind) = take_atom_kind(input)?;
+    let (input, content_length, large_size) = match length {
+        0 => (input, input.len(), false),
+        1 => {
+            let (input, length) = nom::number::complete::be_u64(input)?;
+            (input, (length as usize).saturating_sub(16), true)
+        }
+        _ => (input, (length as usize).saturating_sub(8), false),
+    };
+    let (input, data) = nom::bytes::complete::take(content_length)(input)?;
+
+    let content = if CONTAINER_ATOMS.contains(&&kind) {
+        // Unlike QuickTime files, `meta` atoms in MP4 files have version and flags
+        let header_length = if &kind == b"meta" && data.get(4..8) != Some(b"hdlr") {
+            4usize
+        } else {
+            0
+        };
+        let (data, header) = nom::bytes::complete::take(header_length)(data)?;
+        // Trailing data that isn't an atom is kept as-is.
+        let (trailer, children) = nom::multi::many0(take_atom)(data)?;
+        AtomContent::Children {
+            header: header.to_vec(),
+            children,
+            trailer: trailer.to_vec(),
+        }
+    } else {
+        AtomContent::Data(data.to_vec())
+    };
+    Ok((
+        input,
+        Atom {
+            kind,
+            content,
+            large_size,
+        },
+    ))
+}
+
+impl Atom {
+    fn new_data(kind: &[u8; 4], data: Vec<u8>) -> Self {
+        Self {
+            kind: *kind,
+            content: AtomContent::Data(data),
+            large_size: false,
+        }
+    }
+
+    fn new_container(kind: &[u8; 4], header: Vec<u8>, children: Vec<Atom>) -> Self {
+        Self {
+            kind: *kind,
+            content: AtomContent::Children {
+                header,
+                children,
+                trailer: Vec::new(),
+            },
+            large_size: false,
+        }
+    }
+
+    fn parse(input: &[u8]) -> Result<Self, Error> {
+        let (_, atom) = nom::combinator::all_consuming(take_atom)(input)?;
+        Ok(atom)
+    }
+
+    fn data(&self) -> Option<&[u8]> {
+        match &self.content {
+            AtomContent::Data(data) => Some(data),
+            AtomContent::Children { .. } => None,
+        }
+    }
+
+    fn children(&self) -> &[Atom] {
+        match &self.content {
+            AtomContent::Data(_) => &[],
+            AtomContent::Children { children, .. } => children,
+        }
+    }
+
+    fn child(&self, kind: &[u8; 4]) -> Option<&Atom> {
+        self.children().iter().find(|atom| &atom.kind == kind)
+    }
+
+    /// Returns the child atom of type `kind`, creating it with `create` if it doesn't exist.
+    ///
+    /// Returns `None` if this atom is not a container.
+    fn child_or_insert_with(
+        &mut self,
+        kind: &[u8; 4],
+        create: impl FnOnce() -> Atom,
+    ) -> Option<&mut Atom> {
+        let children = match &mut self.content {
+            AtomContent::Data(_) => return None,
+            AtomContent::Children { children, .. } => children,
+        };
+        let index = match children.iter().position(|atom| &atom.kind == kind) {
+            Some(index) => index,
+            None => {
+                children.push(create());
+                children.len() - 1
+            }
+        };
+        children.get_mut(index)
+    }
+
+    /// Returns the length of the atom content in bytes.
+    fn content_length(&self) -> usize {
+        match &self.content {
+            AtomContent::Data(data) => data.len(),
+            AtomContent::Children {
+                header,
+                children,
+                trailer,
+            } => header.len() + children.iter().map(Atom::length).sum::<usize>() + trailer.len(),
+        }
+    }
+
+    /// Indicates whether the atom length needs to be written as 64-bit "largesize".
+    fn uses_large_size(&self) -> bool {
+        self.large_size || self.content_length() + 8 > u32::MAX as usize
+    }
+
+    /// Returns the length of the serialized atom in bytes.
+    fn length(&self) -> usize {
+        let header_length = if self.uses_large_size() { 16 } else { 8 };
+        header_length + self.content_length()
+    }
+
+    fn write(&self, writer: &mut impl Write) -> Result<usize, Error> {
+        let length = self.length();
+        let mut bytes_written = if self.uses_large_size() {
+            let mut bytes_written = writer.write(&1u32.to_be_bytes())?;
+            bytes_written += writer.write(&self.kind)?;
+            bytes_written += writer.write(&(length as u64).to_be_bytes())?;
+            bytes_written
+        } else {
+            let mut bytes_written = writer.write(&(length as u32).to_be_bytes())?;
+            bytes_written += writer.write(&self.kind)?;
+            bytes_written
+        };
+        match &self.content {
+            AtomContent::Data(data) => {
+                bytes_written += writer.write(data)?;
+            }
+            AtomContent::Children {
+                header,
+                children,
+                trailer,
+            } => {
+                bytes_written += writer.write(header)?;
+                for child in children {
+                    bytes_written += child.write(writer)?;
+                }
+                bytes_written += writer.write(trailer)?;
+            }
+        }
+        Ok(bytes_written)
+    }
+
+    /// Adds `delta` to all chunk offsets in `stco`/`co64` atoms that are greater than or equal to
+    /// `start`.
+    fn shift_chunk_offsets(&mut self, start: u64, delta: i64) -> Result<(), Error> {
+        let invalid_offset = || {
+            Error::IOError(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid MP4 chunk offset",
+            ))
+        };
+        match (&self.kind, &mut self.content) {
+            (b"stco", AtomContent::Data(data)) | (b"co64", AtomContent::Data(data)) => {
+                let entry_length = if &self.kind == b"stco" { 4 } else { 8 };
+                for entry in data
+                    .get_mut(8..)
+                    .unwrap_or(&mut [])
+                    .chunks_exact_mut(entry_length)
+                {
+                    let mut bytes = [0; 8];
+                    bytes[8 - entry_length..].copy_from_slice(entry);
+                    let offset = u64::from_be_bytes(bytes);
+                    if offset < start {
+                        continue;
+                    }
+                    let offset = (offset as i64)
+                        .checked_add(delta)
+                        .filter(|&offset| offset >= 0)
+                        .ok_or_else(invalid_offset)? as u64;
+                    if entry_length == 4 && offset > u64::from(u32::MAX) {
+                        return Err(invalid_offset());
+                    }
+                    entry.copy_from_slice(&offset.to_be_bytes()[8 - entry_length..]);
+                }
+            }
+            (_, AtomContent::Children { children, .. }) => {
+                for child in children {
+                    child.shift_chunk_offsets(start, delta)?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+/// Returns the `(mean, name, data)` content of a freeform (`----`) atom.
+fn parse_freeform(atom: &Atom) -> Option<(&[u8], &[u8], &[u8])> {
+    let mean = atom.child(b"mean")?.data()?.get(4..)?;
+    let name = atom.child(b"name")?.data()?.get(4..)?;
+    let data = atom.child(b"data")?.data()?.get(8..)?;
+    Some((mean, name, data))
+}
+
+/// Creates a new freeform (`----`) atom.
+fn new_freeform(mean: &str, name: &str, value: &[u8]) -> Atom {
+    let mut mean_data = vec![0; 4];
+    mean_data.extend_from_slice(mean.as_bytes());
+    let mut name_data = vec![0; 4];
+    name_data.extend_from_slice(name.as_bytes());
+    let mut data = DATA_TYPE_UTF8.to_be_bytes().to_vec();
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(value);
+    Atom::new_container(
+        b"----",
+        Vec::new(),
+        vec![
+            Atom::new_data(b"mean", mean_data),
+            Atom::new_data(b"name", name_data),
+            Atom::new_data(b"data", data),
+        ],
+    )
+}
+
+/// Set the value of the freeform atom with the given `mean` and `name` in the `ilst` atom to the
+/// tag data written by `write`.
+///
+/// If `write` fails with [`Error::NoTagDataAvailable`], the atom is left untouched.
+fn set_freeform(
+    ilst: &mut Atom,
+    mean: &str,
+    name: &str,
+    write: impl FnOnce(&mut Vec<u8>) -> Result<usize, Error>,
+) -> Result<(), Error> {
+    let mut value = Vec::new();
+    match write(&mut value) {
+        Ok(_) => (),
+        Err(Error::NoTagDataAvailable) => return Ok(()),
+        Err(err) => return Err(err),
+    }
+
+    let atom = new_freeform(mean, name, &value);
+    if let AtomContent::Children { children, .. } = &mut ilst.content {
+        let existing = children.iter().position(|atom| {
+            atom.kind == *b"----"
+                && matches!(parse_freeform(atom), Some((m, n, _))
+                    if m == mean.as_bytes() && n == name.as_bytes())
+        });
+        match existing {
+            Some(index) => children[index] = atom,
+            None => children.push(atom),
+        }
+    }
+    Ok(())
+}
+
+/// Returns the `moov/udta/meta/ilst` atom, creating missing atoms on the way.
+fn ilst_or_insert(moov: &mut Atom) -> Option<&mut Atom> {
+    moov.child_or_insert_with(b"udta", || {
+        Atom::new_container(b"udta", Vec::new(), Vec::new())
+    })?
+    .child_or_insert_with(b"meta", || {
+        let mut hdlr = vec![0; 8];
+        hdlr.extend_from_slice(b"mdirappl");
+        hdlr.extend_from_slice(&[0; 9]);
+        Atom::new_container(b"meta", vec![0; 4], vec![Atom::new_data(b"hdlr", hdlr)])
+    })?
+    .child_or_insert_with(b"ilst", || {
+        Atom::new_container(b"ilst", Vec::new(), Vec::new())
+    })
+}
+
+impl TagContainer {
+    /// Parse all Serato freeform atoms from an MP4 `ilst` atom.
+    ///
+    /// Serato atoms that do not belong to a tag supported by the container are stored as unknown
+    /// tags, all other atoms are ignored.
+    fn parse_mp4_ilst(&mut self, ilst: &Atom) -> Result<(), Error> {
+        for (mean, name, data) in ilst.children().iter().filter_map(parse_freeform) {
+            if mean != SERATO_MEAN.as_bytes() {
+                continue;
+            }
+            let name = String::from_utf8_lossy(name);
+            match name.as_ref() {
+                Analysis::MP4_ATOM_FREEFORM_NAME => self.parse_analysis(data, TagFormat::MP4)?,
+                Autotags::MP4_ATOM_FREEFORM_NAME => self.parse_autotags(data, TagFormat::MP4)?,
+                Beatgrid::MP4_ATOM_FREEFORM_NAME => self.parse_beatgrid(data, TagFormat::MP4)?,
+                Markers::MP4_ATOM_FREEFORM_NAME => self.parse_markers(data, TagFormat::MP4)?,
+                Markers2::MP4_ATOM_FREEFORM_NAME => self.parse_markers2(data, TagFormat::MP4)?,
+                Overview::MP4_ATOM_FREEFORM_NAME => self.parse_overview(data, TagFormat::MP4)?,
+                RelVolAd::MP4_ATOM_FREEFORM_NAME => self.parse_relvolad(data, TagFormat::MP4)?,
+                VidAssoc::MP4_ATOM_FREEFORM_NAME => self.parse_vidassoc(data, TagFormat::MP4)?,
+                name => self.set_unknown_tag(name, data.to_vec(), TagFormat::MP4),
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the Serato freeform atoms in an MP4 `ilst` atom.
+    ///
+    /// Atoms for tags that are not present in the container are left untouched. Unknown tags are
+    /// written back unchanged.
+    fn update_mp4_ilst(&self, ilst: &mut Atom) -> Result<(), Error> {
+        set_freeform(
+            ilst,
+            Analysis::MP4_ATOM_FREEFORM_MEAN,
+            Analysis::MP4_ATOM_FREEFORM_NAME,
+            |w| self.write_analysis(w, TagFormat::MP4),
+        )?;
+        set_freeform(
+            ilst,
+            Autotags::MP4_ATOM_FREEFORM_MEAN,
+            Autotags::MP4_ATOM_FREEFORM_NAME,
+            |w| self.write_autotags(w, TagFormat::MP4),
+        )?;
+        set_freeform(
+            ilst,
+            Beatgrid::MP4_ATOM_FREEFORM_MEAN,
+            Beatgrid::MP4_ATOM_FREEFORM_NAME,
+            |w| self.write_beatgrid(w, TagFormat::MP4),
+        )?;
+        set_freeform(
+            ilst,
+            Markers::MP4_ATOM_FREEFORM_MEAN,
+            Markers::MP4_ATOM_FREEFORM_NAME,
+            |w| self.write_markers(w, TagFormat::MP4),
+        )?;
+        set_freeform(
+            ilst,
+            Markers2::MP4_ATOM_FREEFORM_MEAN,
+            Markers2::MP4_ATOM_FREEFORM_NAME,
+            |w| self.write_markers2(w, TagFormat::MP4),
+        )?;
+        set_freeform(
+            ilst,
+            Overview::MP4_ATOM_FREEFORM_MEAN,
+            Overview::MP4_ATOM_FREEFORM_NAME,
+            |w| self.write_overview(w, TagFormat::MP4),
+        )?;
+        set_freeform(
+            ilst,
+            RelVolAd::MP4_ATOM_FREEFORM_MEAN,
+            RelVolAd::MP4_ATOM_FREEFORM_NAME,
+            |w| self.write_relvolad(w, TagFormat::MP4),
+        )?;
+        set_freeform(
+            ilst,
+            VidAssoc::MP4_ATOM_FREEFORM_MEAN,
+            VidAssoc::MP4_ATOM_FREEFORM_NAME,
+            |w| self.write_vidassoc(w, TagFormat::MP4),
+        )?;
+        for tag in self.unknown_tags(TagFormat::MP4) {
+            set_freeform(ilst, SERATO_MEAN, &tag.name, |w| Ok(w.write(&tag.data)?))?;
+        }
+        Ok(())
+    }
+}
+
+/// Read the headers of all top-level atoms.
+fn read_atom_headers(mut reader: impl Read + Seek) -> Result<Vec<AtomHeader>, Error> {
+    let file_length = reader.seek(SeekFrom::End(0))?;
+    let mut headers = Vec::new();
+    let mut offset = 0;
+    while offset < file_length {
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        let kind = [header[4], header[5], header[6], header[7]];
+        let (header_length, length) =
+            match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                0 => (8, file_length - offset),
+                1 => {
+                    let mut length = [0; 8];
+                    reader.read_exact(&mut length)?;
+                    (16, u64::from_be_bytes(length))
+                }
+                length => (8, u64::from(length)),
+            };
+        if length < header_length || offset + length > file_length {
+            return Err(Error::IOError(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid MP4 atom length",
+            )));
+        }
+        headers.push(AtomHeader {
+            kind,
+            offset,
+            length,
+        });
+        offset += length;
+    }
+    Ok(headers)
+}
+
+/// Read the `moov` atom.
+fn read_moov(mut reader: impl Read + Seek, headers: &[AtomHeader]) -> Result<Atom, Error> {
+    let header = headers
+        .iter()
+        .find(|header| &header.kind == b"moov")
+        .ok_or_else(|| {
+            Error::IOError(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "MP4 file without moov atom",
+            ))
+        })?;
+    reader.seek(SeekFrom::Start(header.offset))?;
+    let mut data = vec![0; header.length as usize];
+    reader.read_exact(&mut data)?;
+    Atom::parse(&data)
+}
+
+/// Read the Serato tags from an MP4 stream.
+pub fn read_mp4_from(mut reader: impl Read + Seek) -> Result<TagContainer, Error> {
+    let headers = read_atom_headers(&mut reader)?;
+    let moov = read_moov(&mut reader, &headers)?;
+    let mut container = TagContainer::new();
+    if let Some(ilst) = moov
+        .child(b"udta")
+        .and_then(|udta| udta.child(b"meta"))
+        .and_then(|meta| meta.child(b"ilst"))
+    {
+        container.parse_mp4_ilst(ilst)?;
+    }
+    Ok(container)
+}
+
+/// Read the Serato tags from an MP4 file.
+pub fn read_mp4(path: &Path) -> Result<TagContainer, Error> {
+    read_mp4_from(BufReader::new(File::open(path)?))
+}
+
+/// Write the Serato tags from `container` to an MP4 file.
+///
+/// The file is updated in place. If the `moov` atom changes size and there is no `free` atom
+/// following it that can absorb the difference, the file is rewritten with updated chunk offsets
+/// through a temporary file, so that the original is left intact if writing fails.
+pub fn write_mp4(path: &Path, container: &TagContainer) -> Result<(), Error> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let headers = read_atom_headers(&mut file)?;
+    let mut moov = read_moov(&mut file, &headers)?;
+    let index = headers
+        .iter()
+        .position(|header| &header.kind == b"moov")
+        .unwrap();
+    let moov_offset = headers[index].offset;
+    let moov_end = moov_offset + headers[index].length;
+
+    let ilst = ilst_or_insert(&mut moov).ok_or(Error::ParseError)?;
+    container.update_mp4_ilst(ilst)?;
+
+    let old_length = headers[index].length as i64;
+    let delta = moov.length() as i64 - old_length;
+    let free = headers
+        .get(index + 1)
+        .filter(|header| &header.kind == b"free" || &header.kind == b"skip");
+
+    let mut data = Vec::new();
+    match free {
+        Some(free) if delta == free.length as i64 || delta <= free.length as i64 - 8 => {
+            moov.write(&mut data)?;
+            if delta < free.length as i64 {
+                let free_length = (free.length as i64 - delta) as usize;
+                Atom::new_data(b"free", vec![0; free_length - 8]).write(&mut data)?;
+            }
+            file.seek(SeekFrom::Start(moov_offset))?;
+            file.write_all(&data)?;
+        }
+        _ if delta == 0 => {
+            moov.write(&mut data)?;
+            file.seek(SeekFrom::Start(moov_offset))?;
+            file.write_all(&data)?;
+        }
+        _ => {
+            moov.shift_chunk_offsets(moov_end, delta)?;
+            data.resize(moov_offset as usize, 0);
+            file.seek(SeekFrom::Start(0))?;
+            file.read_exact(&mut data)?;
+            moov.write(&mut data)?;
+            file.seek(SeekFrom::Start(moov_end))?;
+            file.read_to_end(&mut data)?;
+            drop(file);
+            return replace_file(path, &data);
+        }
+    }
+    file.flush()?;
+    Ok(())
+}
@@ -0,0 +1,348 @@
+//! Reading and writing Serato tags in Ogg Vorbis and Ogg Opus files.
+//!
+//! Serato stores its tags as `serato_*` comments in the comment header packet of the logical
+//! bitstream. When writing, the comment header (and the Vorbis setup header that usually shares its
+//! pages) is repaginated, and the sequence numbers and CRCs of all following pages of the bitstream
+//! are updated if the number of header pages changed.
+
+use super::replace_file;
+use super::vorbis::VorbisComment;
+use crate::error::Error;
+use crate::tag::format::ogg::OggTag;
+use crate::tag::{Analysis, Markers2, TagContainer, TagFormat};
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+
+/// Magic bytes at the start of each Ogg page.
+const PAGE_MARKER: &[u8; 4] = b"OggS";
+/// Flag in the page header that marks pages that start with a continued packet.
+const PAGE_CONTINUED: u8 = 0x01;
+/// Maximum number of segments in a single page.
+const PAGE_MAX_SEGMENTS: usize = 255;
+/// Granule position of pages on which no packet ends.
+const GRANULE_POSITION_NONE: u64 = u64::MAX;
+/// Magic bytes at the start of the Vorbis identification header.
+const VORBIS_IDENTIFICATION_HEADER: &[u8] = b"\x01vorbis";
+/// Magic bytes at the start of the Vorbis comment header.
+const VORBIS_COMMENT_HEADER: &[u8] = b"\x03vorbis";
+/// Magic bytes at the start of the Opus identification header.
+const OPUS_IDENTIFICATION_HEADER: &[u8] = b"OpusHead";
+/// Magic bytes at the start of the Opus comment header.
+const OPUS_COMMENT_HEADER: &[u8] = b"OpusTags";
+/// Prefix of the comment names used by Serato.
+const COMMENT_PREFIX: &str = "serato_";
+
+/// An Ogg page.
+#[derive(Debug, Clone)]
+struct Page {
+    header_type: u8,
+    granule_position: u64,
+    serial: u32,
+    sequence: u32,
+    segments: Vec<u8>,
+    data: Vec<u8>,
+}
+
+/// Returns the CRC checksum used by Ogg pages (polynomial `0x04C11DB7`, no reflection, no final
+/// XOR).
+fn crc32(data: &[u8]) -> u32 {
+    data.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ (u32::from(byte) << 24), |crc, _| {
+            if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+#[test]
+fn test_crc32() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0x89A1_897F);
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::IOError(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+impl Page {
+    /// Read the next page. Returns `None` at the end of the stream.
+    fn read(mut reader: impl Read) -> Result<Option<Self>, Error> {
+        let mut header = [0; 27];
+        match reader.read_exact(&mut header) {
+            Ok(()) => (),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        if &header[..4] != PAGE_MARKER || header[4] != 0 {
+            return Err(invalid_data("Invalid Ogg page header"));
+        }
+
+        let u32_at =
+            |i: usize| u32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+        let mut granule_position = [0; 8];
+        granule_position.copy_from_slice(&header[6..14]);
+        let mut segments = vec![0; usize::from(header[26])];
+        reader.read_exact(&mut segments)?;
+        let mut data = vec![0; segments.iter().map(|&length| usize::from(length)).sum()];
+        reader.read_exact(&mut data)?;
+
+        let page = Page {
+            header_type: header[5],
+            granule_position: u64::from_le_bytes(granule_position),
+            serial: u32_at(14),
+            sequence: u32_at(18),
+            segments,
+            data,
+        };
+        let mut buffer = Vec::new();
+        page.write(&mut buffer)?;
+        if u32_at(22) != u32::from_le_bytes([buffer[22], buffer[23], buffer[24], buffer[25]]) {
+            return Err(invalid_data("Ogg page checksum mismatch"));
+        }
+        Ok(Some(page))
+    }
+
+    /// Write the page and calculate its checksum.
+    fn write(&self, mut writer: impl Write) -> Result<usize, Error> {
+        let mut buffer = PAGE_MARKER.to_vec();
+        buffer.push(0);
+        buffer.push(self.header_type);
+        buffer.extend_from_slice(&self.granule_position.to_le_bytes());
+        buffer.extend_from_slice(&self.serial.to_le_bytes());
+        buffer.extend_from_slice(&self.sequence.to_le_bytes());
+        buffer.extend_from_slice(&[0; 4]);
+        buffer.push(self.segments.len() as u8);
+        buffer.extend_from_slice(&self.segments);
+        buffer.extend_from_slice(&self.data);
+        let crc = crc32(&buffer);
+        buffer[22..26].copy_from_slice(&crc.to_le_bytes());
+        Ok(writer.write(&buffer)?)
+    }
+}
+
+/// Read all pages from a stream.
+fn read_pages(mut reader: impl Read) -> Result<Vec<Page>, Error> {
+    let mut pages = Vec::new();
+    while let Some(page) = Page::read(&mut reader)? {
+        pages.push(page);
+    }
+    Ok(pages)
+}
+
+/// The header packets of a logical bitstream.
+struct HeaderPackets {
+    packets: Vec<Vec<u8>>,
+    /// Number of pages that the header packets span.
+    page_count: usize,
+}
+
+/// Returns the header packets of the first logical bitstream, or `None` if the pages don't contain
+/// all header packets.
+///
+/// The codec (Vorbis or Opus) is detected from the identification header.
+fn header_packets<'a>(
+    pages: impl IntoIterator<Item = &'a Page>,
+) -> Result<Option<HeaderPackets>, Error> {
+    let mut packets: Vec<Vec<u8>> = Vec::new();
+    let mut packet = Vec::new();
+    let mut packet_count = None;
+    for (page_index, page) in pages.into_iter().enumerate() {
+        let mut offset = 0;
+        for &length in &page.segments {
+            let length = usize::from(length);
+            packet.extend_from_slice(&page.data[offset..offset + length]);
+            offset += length;
+            if length == 255 {
+                continue;
+            }
+            packets.push(std::mem::take(&mut packet));
+            if packets.len() == 1 {
+                packet_count = if packets[0].starts_with(VORBIS_IDENTIFICATION_HEADER) {
+                    Some(3)
+                } else if packets[0].starts_with(OPUS_IDENTIFICATION_HEADER) {
+                    Some(2)
+                } else {
+                    return Err(invalid_data("Unsupported Ogg codec"));
+                };
+            }
+            if packet_count == Some(packets.len()) {
+                if offset != page.data.len() {
+                    return Err(invalid_data(
+                        "Ogg header packets do not end on page boundary",
+                    ));
+                }
+                return Ok(Some(HeaderPackets {
+                    packets,
+                    page_count: page_index + 1,
+                }));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Splits the comment header packet into the magic bytes, the comment and the remaining data (the
+/// framing bit for Vorbis or the padding for Opus).
+fn parse_comment_packet(packet: &[u8]) -> Result<(&[u8], VorbisComment, &[u8]), Error> {
+    let magic = [VORBIS_COMMENT_HEADER, OPUS_COMMENT_HEADER]
+        .iter()
+        .find(|magic| packet.starts_with(magic))
+        .ok_or_else(|| invalid_data("Invalid Ogg comment header"))?;
+    let (comment, rest) = VorbisComment::parse(&packet[magic.len()..])?;
+    Ok((magic, comment, rest))
+}
+
+/// Split packets into pages with at most 255 segments each.
+fn paginate(packets: &[Vec<u8>], serial: u32, first_sequence: u32) -> Vec<Page> {
+    // Each segment is stored together with a flag that indicates if it ends a packet
+    let mut segments: Vec<(&[u8], bool)> = Vec::new();
+    for packet in packets {
+        let full_segments = packet.len() / 255;
+        for i in 0..full_segments {
+            segments.push((&packet[i * 255..(i + 1) * 255], false));
+        }
+        segments.push((&packet[full_segments * 255..], true));
+    }
+
+    let mut pages: Vec<Page> = Vec::new();
+    for chunk in segments.chunks(PAGE_MAX_SEGMENTS) {
+        let continued = matches!(pages.last(), Some(page) if page.segments.last() == Some(&255));
+        let packet_ends = chunk.iter().any(|&(_, is_last)| is_last);
+        pages.push(Page {
+            header_type: if continued { PAGE_CONTINUED } else { 0 },
+            granule_position: if packet_ends {
+                0
+            } else {
+                GRANULE_POSITION_NONE
+            },
+            serial,
+            sequence: first_sequence + pages.len() as u32,
+            segments: chunk.iter().map(|(data, _)| data.len() as u8).collect(),
+            data: chunk
+                .iter()
+                .flat_map(|(data, _)| data.iter())
+                .copied()
+                .collect(),
+        });
+    }
+    pages
+}
+
+impl TagContainer {
+    /// Parse all Serato comments from an Ogg comment header.
+    ///
+    /// Serato comments that do not belong to a tag supported by the container are stored as
+    /// unknown tags, all other comments are ignored.
+    fn parse_ogg_comment(&mut self, comment: &VorbisComment) -> Result<(), Error> {
+        for (name, value) in comment.iter() {
+            let name = String::from_utf8_lossy(name);
+            match name.to_ascii_lowercase().as_str() {
+                Analysis::OGG_COMMENT => self.parse_analysis(value, TagFormat::Ogg)?,
+                Markers2::OGG_COMMENT => self.parse_markers2(value, TagFormat::Ogg)?,
+                n if n.starts_with(COMMENT_PREFIX) => {
+                    self.set_unknown_tag(&name, value.to_vec(), TagFormat::Ogg)
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the Serato comments in an Ogg comment header.
+    ///
+    /// Comments for tags that are not present in the container are left untouched. Unknown tags
+    /// are written back unchanged.
+    fn update_ogg_comment(&self, comment: &mut VorbisComment) -> Result<(), Error> {
+        comment.set_tag(Analysis::OGG_COMMENT, |w| {
+            self.write_analysis(w, TagFormat::Ogg)
+        })?;
+        comment.set_tag(Markers2::OGG_COMMENT, |w| {
+            self.write_markers2(w, TagFormat::Ogg)
+        })?;
+        for tag in self.unknown_tags(TagFormat::Ogg) {
+            comment.set(&tag.name, &tag.data);
+        }
+        Ok(())
+    }
+}
+
+/// Read the Serato tags from an Ogg stream.
+pub fn read_ogg_from(mut reader: impl Read) -> Result<TagContainer, Error> {
+    let mut pages: Vec<Page> = Vec::new();
+    let packets = loop {
+        let page = Page::read(&mut reader)?
+            .ok_or_else(|| invalid_data("Incomplete Ogg header packets"))?;
+        if matches!(pages.first(), Some(first) if first.serial != page.serial) {
+            continue;
+        }
+        pages.push(page);
+        if let Some(header) = header_packets(&pages)? {
+            break header.packets;
+        }
+    };
+
+    let (_, comment, _) = parse_comment_packet(&packets[1])?;
+    let mut container = TagContainer::new();
+    container.parse_ogg_comment(&comment)?;
+    Ok(container)
+}
+
+/// Read the Serato tags from an Ogg file.
+pub fn read_ogg(path: &Path) -> Result<TagContainer, Error> {
+    read_ogg_from(BufReader::new(File::open(path)?))
+}
+
+/// Write the Serato tags from `container` to an Ogg file.
+pub fn write_ogg(path: &Path, container: &TagContainer) -> Result<(), Error> {
+    let pages = read_pages(BufReader::new(File::open(path)?))?;
+    let serial = pages
+        .first()
+        .map(|page| page.serial)
+        .ok_or_else(|| invalid_data("Empty Ogg file"))?;
+    let stream_pages = || pages.iter().filter(|page| page.serial == serial);
+
+    let HeaderPackets {
+        mut packets,
+        page_count: header_page_count,
+    } = header_packets(stream_pages())?
+        .ok_or_else(|| invalid_data("Incomplete Ogg header packets"))?;
+    let (magic, mut comment, rest) = parse_comment_packet(&packets[1])?;
+    container.update_ogg_comment(&mut comment)?;
+    let mut packet = magic.to_vec();
+    comment.write(&mut packet)?;
+    packet.extend_from_slice(rest);
+    packets[1] = packet;
+
+    // The first page only contains the identification header and is kept as is.
+    let first_sequence = stream_pages().nth(1).map_or(1, |page| page.sequence);
+    let header_pages = paginate(&packets[1..], serial, first_sequence);
+    let sequence_delta = header_pages.len() as i64 - (header_page_count - 1) as i64;
+
+    let mut writer = Vec::new();
+    let mut stream_page_index = 0;
+    for page in &pages {
+        if page.serial != serial {
+            page.write(&mut writer)?;
+            continue;
+        }
+        stream_page_index += 1;
+        if stream_page_index == 2 {
+            for header_page in &header_pages {
+                header_page.write(&mut writer)?;
+            }
+        }
+        if stream_page_index == 1 {
+            page.write(&mut writer)?;
+        } else if stream_page_index > header_page_count {
+            let mut page = page.clone();
+            page.sequence = (i64::from(page.sequence) + sequence_delta) as u32;
+            page.write(&mut writer)?;
+        }
+    }
+    replace_file(path, &writer)
+}
@@ -0,0 +1,164 @@
+//! Vorbis comments, as used by FLAC and Ogg files.
+extern crate nom;
+
+use crate::error::Error;
+use crate::util::Res;
+use std::convert::TryFrom;
+use std::io;
+
+/// A Vorbis comment header, consisting of a vendor string and a list of `NAME=value` comments.
+///
+/// The comments are kept as raw bytes, so that comments that are not touched are written back
+/// unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VorbisComment {
+    pub vendor: Vec<u8>,
+    pub comments: Vec<Vec<u8>>,
+}
+
+fn take_vorbis_string(input: &[u8]) -> Res<&[u8], &[u8]> {
+    nom::multi::length_data(nom::number::complete::le_u32)(input)
+}
+
+fn take_vorbis_comment(input: &[u8]) -> Res<&[u8], VorbisComment> {
+    let (input, vendor) = take_vorbis_string(input)?;
+    let (input, comments) =
+        nom::multi::length_count(nom::number::complete::le_u32, take_vorbis_string)(input)?;
+    let comment = VorbisComment {
+        vendor: vendor.to_vec(),
+        comments: comments.into_iter().map(|c| c.to_vec()).collect(),
+    };
+    Ok((input, comment))
+}
+
+/// Splits a comment into its name and value.
+fn split_comment(comment: &[u8]) -> Option<(&[u8], &[u8])> {
+    let index = comment.iter().position(|&byte| byte == b'=')?;
+    Some((&comment[..index], &comment[index + 1..]))
+}
+
+#[test]
+fn test_split_comment() {
+    assert_eq!(
+        split_comment(b"SERATO_BEATGRID=abc="),
+        Some((&b"SERATO_BEATGRID"[..], &b"abc="[..]))
+    );
+    assert_eq!(split_comment(b"TITLE="), Some((&b"TITLE"[..], &b""[..])));
+    assert_eq!(split_comment(b"invalid"), None);
+}
+
+fn write_vorbis_string(mut writer: impl io::Write, data: &[u8]) -> Result<usize, Error> {
+    let length = u32::try_from(data.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Vorbis comment too long"))?;
+    let mut bytes_written = writer.write(&length.to_le_bytes())?;
+    bytes_written += writer.write(data)?;
+    Ok(bytes_written)
+}
+
+impl VorbisComment {
+    /// Creates a new, empty comment header with the given vendor string.
+    pub fn new(vendor: &str) -> Self {
+        Self {
+            vendor: vendor.as_bytes().to_vec(),
+            comments: Vec::new(),
+        }
+    }
+
+    /// Parse a comment header and return it together with the remaining input.
+    pub fn parse(input: &[u8]) -> Result<(Self, &[u8]), Error> {
+        let (input, comment) = take_vorbis_comment(input)?;
+        Ok((comment, input))
+    }
+
+    /// Serialize the comment header.
+    pub fn write(&self, mut writer: impl io::Write) -> Result<usize, Error> {
+        let mut bytes_written = write_vorbis_string(&mut writer, &self.vendor)?;
+        let count = u32::try_from(self.comments.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Too many comments"))?;
+        bytes_written += writer.write(&count.to_le_bytes())?;
+        for comment in &self.comments {
+            bytes_written += write_vorbis_string(&mut writer, comment)?;
+        }
+        Ok(bytes_written)
+    }
+
+    /// Returns an iterator over all `(name, value)` pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.comments
+            .iter()
+            .filter_map(|comment| split_comment(comment))
+    }
+
+    /// Replace all comments called `name` (case-insensitive) with a single comment containing
+    /// `value`.
+    ///
+    /// The new comment takes the place of the first comment it replaces, or is appended if there
+    /// was none.
+    pub fn set(&mut self, name: &str, value: &[u8]) {
+        let mut comment = name.as_bytes().to_vec();
+        comment.push(b'=');
+        comment.extend_from_slice(value);
+
+        let has_name = |c: &Vec<u8>| matches!(split_comment(c), Some((n, _)) if n.eq_ignore_ascii_case(name.as_bytes()));
+        match self.comments.iter().position(has_name) {
+            Some(index) => {
+                self.comments[index] = comment;
+                let mut i = index + 1;
+                while i < self.comments.len() {
+                    if has_name(&self.comments[i]) {
+                        self.comments.remove(i);
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            None => self.comments.push(comment),
+        }
+    }
+
+    /// Set the comment `name` to the tag data written by `write`.
+    ///
+    /// If `write` fails with [`Error::NoTagDataAvailable`], the comment is left untouched.
+    pub fn set_tag(
+        &mut self,
+        name: &str,
+        write: impl FnOnce(&mut Vec<u8>) -> Result<usize, Error>,
+    ) -> Result<(), Error> {
+        let mut value = Vec::new();
+        match write(&mut value) {
+            Ok(_) => {
+                self.set(name, &value);
+                Ok(())
+            }
+            Err(Error::NoTagDataAvailable) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[test]
+fn test_vorbis_comment_roundtrip() {
+    let mut comment = VorbisComment::new("vendor");
+    comment.set("TITLE", b"Test");
+    comment.set("SERATO_BEATGRID", b"old");
+    comment.set("ARTIST", b"Someone");
+    comment.comments.push(b"serato_beatgrid=duplicate".to_vec());
+    comment.set("SERATO_BEATGRID", b"new");
+    assert_eq!(
+        comment.comments,
+        vec![
+            b"TITLE=Test".to_vec(),
+            b"SERATO_BEATGRID=new".to_vec(),
+            b"ARTIST=Someone".to_vec(),
+        ]
+    );
+
+    let mut data = Vec::new();
+    let bytes_written = comment.write(&mut data).unwrap();
+    assert_eq!(bytes_written, data.len());
+    assert_eq!(&data[..10], b"\x06\x00\x00\x00vendor");
+    data.push(0x01);
+    let (parsed, rest) = VorbisComment::parse(&data).unwrap();
+    assert_eq!(parsed, comment);
+    assert_eq!(rest, &[0x01]);
+}
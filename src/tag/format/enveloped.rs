@@ -60,19 +60,17 @@ pub fn base64_encode(mut writer: impl io::Write, input: &[u8]) -> Result<usize,
     let chunks = input.chunks(54);
     let last_chunk_index = chunks.len() - 1;
     for (i, chunk) in chunks.enumerate() {
-        let mut buf = Vec::new();
-        buf.resize(72, 0);
-        let bytes_encoded = base64::encode_config_slice(&chunk, BASE64_FORGIVING, &mut buf);
+        let mut buf = vec![0; 72];
+        let bytes_encoded = base64::encode_config_slice(chunk, BASE64_FORGIVING, &mut buf);
         bytes_written += writer.write(&buf[..bytes_encoded])?;
         if i == last_chunk_index {
             if bytes_encoded % 4 != 2 {
                 bytes_written += writer.write(b"A")?;
             }
         } else {
-            bytes_written += writer.write(&[b'\n'])?;
+            bytes_written += writer.write(b"\n")?;
         }
     }
-    println!("{}", bytes_written);
     Ok(bytes_written)
 }
 
@@ -7,15 +7,18 @@ use super::enveloped::EnvelopedTag;
 use crate::error::Error;
 use std::io;
 
+/// The mean part of the freeform `MP4_ATOM`s that Serato stores its tags in.
+pub const SERATO_MEAN: &str = "com.serato.dj";
+
 pub trait MP4Tag: EnvelopedTag {
     /// The mean part of the freeform `MP4_ATOM` that this data is stored in.
-    const MP4_ATOM_FREEFORM_MEAN: &'static str = "com.serato.dj";
+    const MP4_ATOM_FREEFORM_MEAN: &'static str = SERATO_MEAN;
 
     /// The mean part of the freeform `MP4_ATOM` that this data is stored in.
     const MP4_ATOM_FREEFORM_NAME: &'static str;
 
     fn parse_mp4(input: &[u8]) -> Result<Self, Error> {
-        Self::parse_enveloped(&input)
+        Self::parse_enveloped(input)
     }
 
     fn write_mp4(&self, writer: impl io::Write) -> Result<usize, Error> {
@@ -2,14 +2,14 @@
 use super::color::Color;
 
 /// Represents 2-Byte version value.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Version {
     pub major: u8,
     pub minor: u8,
 }
 
 /// A [cue point](https://support.serato.com/hc/en-us/articles/360000067696-Cue-Points).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Cue {
     pub index: u8,
     pub position_millis: u32,
@@ -18,7 +18,7 @@ pub struct Cue {
 }
 
 /// A [saved loops](https://serato.com/latest/blog/17885/pro-tip-trigger-saved-loops).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Loop {
     pub index: u8,
     pub start_position_millis: u32,
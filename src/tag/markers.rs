@@ -6,7 +6,7 @@
 
 use super::color::Color;
 use super::format::{enveloped, id3, mp4, Tag};
-use super::generic::Version;
+use super::generic::{Cue, Loop, Version};
 use super::serato32;
 use super::util::{take_color, take_version, write_color, write_version};
 use crate::error::Error;
@@ -69,7 +69,55 @@ pub struct Markers {
     pub track_color: Color,
 }
 
+/// Number of cue slots in the `Serato Markers_` tag.
+pub const CUE_SLOTS: usize = 5;
+/// Number of loop slots in the `Serato Markers_` tag.
+pub const LOOP_SLOTS: usize = 9;
+/// The color that Serato uses for all loops in the `Serato Markers_` tag.
+pub const LOOP_COLOR: Color = Color {
+    red: 0x27,
+    green: 0xAA,
+    blue: 0xE1,
+};
+/// Tag version written by [`Markers::new`].
+const MARKERS_VERSION: Version = Version { major: 2, minor: 5 };
+
+impl Marker {
+    /// Returns an unset cue marker.
+    fn empty_cue() -> Self {
+        Self {
+            start_position_millis: None,
+            end_position_millis: None,
+            color: Color::from(0x000000),
+            marker_type: MarkerType::Invalid,
+            is_locked: false,
+        }
+    }
+
+    /// Returns an unset loop marker.
+    fn empty_loop() -> Self {
+        Self {
+            marker_type: MarkerType::Loop,
+            ..Self::empty_cue()
+        }
+    }
+}
+
 impl Markers {
+    /// Create a new tag with all cue and loop slots unset.
+    ///
+    /// This uses the same layout that Serato writes, i.e. [`CUE_SLOTS`] cue markers followed by
+    /// [`LOOP_SLOTS`] loop markers.
+    pub fn new(track_color: Color) -> Self {
+        let mut entries: Vec<Marker> = (0..CUE_SLOTS).map(|_| Marker::empty_cue()).collect();
+        entries.extend((0..LOOP_SLOTS).map(|_| Marker::empty_loop()));
+        Self {
+            version: MARKERS_VERSION,
+            entries,
+            track_color,
+        }
+    }
+
     pub fn cues(&self) -> Vec<(u8, &Marker)> {
         let mut index: u8 = 0;
         let mut cues = Vec::new();
@@ -101,6 +149,96 @@ impl Markers {
     pub fn track_color(&self) -> Color {
         self.track_color
     }
+
+    /// Set the cue slot for the cue's index.
+    ///
+    /// The label is not stored in this tag. Returns `false` if there is no slot for that index
+    /// (i.e. the index is 5 or higher).
+    pub fn set_cue(&mut self, cue: &Cue) -> bool {
+        match self.cue_slot_mut(cue.index) {
+            Some(marker) => {
+                *marker = Marker {
+                    start_position_millis: Some(cue.position_millis),
+                    end_position_millis: None,
+                    color: cue.color,
+                    marker_type: MarkerType::Cue,
+                    is_locked: false,
+                };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Unset the cue slot with the given index.
+    ///
+    /// Returns `false` if there is no slot for that index.
+    pub fn remove_cue(&mut self, index: u8) -> bool {
+        match self.cue_slot_mut(index) {
+            Some(marker) => {
+                *marker = Marker::empty_cue();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the loop slot for the loop's index.
+    ///
+    /// The label is not stored in this tag and the color is always [`LOOP_COLOR`]. Returns
+    /// `false` if there is no slot for that index (i.e. the index is 9 or higher).
+    pub fn set_loop(&mut self, saved_loop: &Loop) -> bool {
+        match self.loop_slot_mut(saved_loop.index) {
+            Some(marker) => {
+                *marker = Marker {
+                    start_position_millis: Some(saved_loop.start_position_millis),
+                    end_position_millis: Some(saved_loop.end_position_millis),
+                    color: LOOP_COLOR,
+                    marker_type: MarkerType::Loop,
+                    is_locked: saved_loop.is_locked,
+                };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Unset the loop slot with the given index.
+    ///
+    /// Returns `false` if there is no slot for that index.
+    pub fn remove_loop(&mut self, index: u8) -> bool {
+        match self.loop_slot_mut(index) {
+            Some(marker) => {
+                *marker = Marker::empty_loop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the track color.
+    pub fn set_track_color(&mut self, color: Color) {
+        self.track_color = color;
+    }
+
+    /// Returns the cue marker with the given index (using the same numbering as [`Self::cues`]).
+    fn cue_slot_mut(&mut self, index: u8) -> Option<&mut Marker> {
+        self.entries
+            .iter_mut()
+            .filter(|marker| {
+                marker.marker_type == MarkerType::Invalid || marker.marker_type == MarkerType::Cue
+            })
+            .nth(usize::from(index))
+    }
+
+    /// Returns the loop marker with the given index (using the same numbering as
+    /// [`Self::loops`]).
+    fn loop_slot_mut(&mut self, index: u8) -> Option<&mut Marker> {
+        self.entries
+            .iter_mut()
+            .filter(|marker| marker.marker_type == MarkerType::Loop)
+            .nth(usize::from(index))
+    }
 }
 
 impl Tag for Markers {
@@ -112,7 +250,7 @@ impl Tag for Markers {
     }
 
     fn write(&self, writer: impl io::Write) -> Result<usize, Error> {
-        write_markers(writer, &self)
+        write_markers(writer, self)
     }
 }
 
@@ -132,7 +270,7 @@ impl mp4::MP4Tag for Markers {
 
     fn write_mp4(&self, writer: impl io::Write) -> Result<usize, Error> {
         let mut buffer = Cursor::new(vec![]);
-        write_markers_mp4(&mut buffer, &self)?;
+        write_markers_mp4(&mut buffer, self)?;
         let plain_data = &buffer.get_ref()[..];
         enveloped::envelope_encode_with_name(writer, plain_data, Self::NAME)
     }
@@ -303,7 +441,7 @@ fn take_marker(input: &[u8]) -> Res<&[u8], Marker> {
 
 /// Parses the data into a `Markers` struct, consuming the whole input slice.
 fn take_markers(input: &[u8]) -> Res<&[u8], Markers> {
-    let (input, version) = take_version(&input)?;
+    let (input, version) = take_version(input)?;
     let (input, entries) =
         nom::multi::length_count(nom::number::complete::be_u32, take_marker)(input)?;
     let (input, track_color) = nom::combinator::all_consuming(serato32::take_color)(input)?;
@@ -354,7 +492,7 @@ fn take_marker_mp4(input: &[u8]) -> Res<&[u8], Marker> {
 
 /// Parses the data into a `Markers` struct, consuming the whole input slice (MP4 version).
 fn take_markers_mp4(input: &[u8]) -> Res<&[u8], Markers> {
-    let (input, version) = take_version(&input)?;
+    let (input, version) = take_version(input)?;
     let (input, entries) =
         nom::multi::length_count(nom::number::complete::be_u32, take_marker_mp4)(input)?;
     let (input, _) = nom::bytes::complete::tag(b"\0")(input)?;
@@ -430,7 +568,7 @@ pub fn write_markers(mut writer: impl io::Write, markers: &Markers) -> Result<us
     let num_markers = markers.entries.len() as u32;
     bytes_written += writer.write(&num_markers.to_be_bytes())?;
     for marker in &markers.entries {
-        bytes_written += write_marker(&mut writer, &marker)?;
+        bytes_written += write_marker(&mut writer, marker)?;
     }
     bytes_written += serato32::write_color(writer, &markers.track_color)?;
     Ok(bytes_written)
@@ -441,9 +579,44 @@ pub fn write_markers_mp4(mut writer: impl io::Write, markers: &Markers) -> Resul
     let num_markers = markers.entries.len() as u32;
     bytes_written += writer.write(&num_markers.to_be_bytes())?;
     for marker in &markers.entries {
-        bytes_written += write_marker_mp4(&mut writer, &marker)?;
+        bytes_written += write_marker_mp4(&mut writer, marker)?;
     }
     bytes_written += writer.write(b"\x00")?;
     bytes_written += write_color(writer, &markers.track_color)?;
     Ok(bytes_written)
 }
+
+#[test]
+fn test_markers_new() {
+    use super::format::id3::ID3Tag;
+
+    let mut markers = Markers::new(Color::from(0xFFFFFF));
+    let mut output = Vec::new();
+    markers.write_id3(&mut output).unwrap();
+    assert_eq!(
+        output,
+        include_bytes!("../../tests/data/tags/markers/analyzed.id3.bin").to_vec()
+    );
+
+    let saved_loop = Loop {
+        index: 8,
+        start_position_millis: 0,
+        end_position_millis: 2086,
+        color: Color::from(0xCC0000),
+        is_locked: true,
+        label: String::from("Ignored"),
+    };
+    assert!(markers.set_loop(&saved_loop));
+    assert!(!markers.set_loop(&Loop {
+        index: 9,
+        ..saved_loop
+    }));
+    let (index, marker) = markers.loops().pop().unwrap();
+    assert_eq!(index, 8);
+    assert_eq!(marker.color, LOOP_COLOR);
+    assert!(marker.is_locked);
+
+    assert!(markers.remove_loop(8));
+    assert!(!markers.remove_cue(5));
+    assert_eq!(markers.entries.len(), CUE_SLOTS + LOOP_SLOTS);
+}
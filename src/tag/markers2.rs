@@ -5,6 +5,7 @@
 //! Note that some of this information is also stored in `Serato Markers_`, and Serato will prefer that data over the information stored in `Serato Markers2` if it is present.
 //!
 //! The minimum length of this tag seems to be 470 bytes, and shorter contents are padded with null bytes.
+//! This does not apply to Ogg comments, which are not padded.
 
 use super::color::Color;
 use super::format::{enveloped, flac, id3, mp4, ogg, Tag};
@@ -87,7 +88,43 @@ pub struct Markers2 {
     pub content: Markers2Content,
 }
 
+/// Tag version written by [`Markers2::new`].
+const MARKERS2_VERSION: Version = Version { major: 1, minor: 1 };
+/// Minimum tag size in ID3, FLAC and MP4 tags (shorter contents are padded with null bytes).
+const MARKERS2_MIN_SIZE: usize = 470;
+
+impl Marker {
+    /// Returns the key for sorting markers in the order Serato writes them (`COLOR`, `CUE`,
+    /// `LOOP`, `BPMLOCK`, `FLIP`), or `None` for unknown markers.
+    fn sort_key(&self) -> Option<(u8, u8)> {
+        match self {
+            Marker::Color(_) => Some((0, 0)),
+            Marker::Cue(cue) => Some((1, cue.index)),
+            Marker::Loop(saved_loop) => Some((2, saved_loop.index)),
+            Marker::BPMLock(_) => Some((3, 0)),
+            Marker::Flip(flip) => Some((4, flip.index)),
+            Marker::Unknown(_) => None,
+        }
+    }
+}
+
 impl Markers2 {
+    /// Create a new tag that only contains the track color and an unlocked BPM, like the tags that
+    /// Serato writes for freshly analyzed tracks.
+    pub fn new(track_color: Color) -> Self {
+        Self {
+            version: Some(MARKERS2_VERSION),
+            size: 0,
+            content: Markers2Content {
+                version: MARKERS2_VERSION,
+                markers: vec![
+                    Marker::Color(TrackColorMarker { color: track_color }),
+                    Marker::BPMLock(BPMLockMarker { is_locked: false }),
+                ],
+            },
+        }
+    }
+
     pub fn bpm_locked(&self) -> Option<bool> {
         for marker in &self.content.markers {
             if let Marker::BPMLock(m) = marker {
@@ -135,6 +172,76 @@ impl Markers2 {
         }
         None
     }
+
+    /// Add the cue, replacing an existing cue with the same index.
+    pub fn set_cue(&mut self, cue: Cue) {
+        self.remove_cue(cue.index);
+        self.insert_marker(Marker::Cue(cue));
+    }
+
+    /// Remove the cue with the given index and return it.
+    pub fn remove_cue(&mut self, index: u8) -> Option<Cue> {
+        let position = self
+            .content
+            .markers
+            .iter()
+            .position(|marker| matches!(marker, Marker::Cue(cue) if cue.index == index))?;
+        match self.content.markers.remove(position) {
+            Marker::Cue(cue) => Some(cue),
+            _ => None,
+        }
+    }
+
+    /// Add the loop, replacing an existing loop with the same index.
+    pub fn set_loop(&mut self, saved_loop: Loop) {
+        self.remove_loop(saved_loop.index);
+        self.insert_marker(Marker::Loop(saved_loop));
+    }
+
+    /// Remove the loop with the given index and return it.
+    pub fn remove_loop(&mut self, index: u8) -> Option<Loop> {
+        let position = self.content.markers.iter().position(
+            |marker| matches!(marker, Marker::Loop(saved_loop) if saved_loop.index == index),
+        )?;
+        match self.content.markers.remove(position) {
+            Marker::Loop(saved_loop) => Some(saved_loop),
+            _ => None,
+        }
+    }
+
+    /// Set the track color, adding a `COLOR` marker if necessary.
+    pub fn set_track_color(&mut self, color: Color) {
+        for marker in &mut self.content.markers {
+            if let Marker::Color(m) = marker {
+                m.color = color;
+                return;
+            }
+        }
+        self.insert_marker(Marker::Color(TrackColorMarker { color }));
+    }
+
+    /// Set the BPM lock status, adding a `BPMLOCK` marker if necessary.
+    pub fn set_bpm_locked(&mut self, is_locked: bool) {
+        for marker in &mut self.content.markers {
+            if let Marker::BPMLock(m) = marker {
+                m.is_locked = is_locked;
+                return;
+            }
+        }
+        self.insert_marker(Marker::BPMLock(BPMLockMarker { is_locked }));
+    }
+
+    /// Insert the marker in front of the first marker that Serato would write after it.
+    fn insert_marker(&mut self, marker: Marker) {
+        let key = marker.sort_key();
+        let position = self
+            .content
+            .markers
+            .iter()
+            .position(|m| m.sort_key().is_some() && m.sort_key() > key)
+            .unwrap_or(self.content.markers.len());
+        self.content.markers.insert(position, marker);
+    }
 }
 
 impl Tag for Markers2 {
@@ -146,7 +253,7 @@ impl Tag for Markers2 {
     }
 
     fn write(&self, writer: impl io::Write) -> Result<usize, Error> {
-        write_markers2(writer, &self)
+        write_markers2(writer, self)
     }
 }
 
@@ -251,10 +358,10 @@ fn decode_base64_chunks(
         }
         let mut buf = [0; 54];
         // TODO: Add proper error handling here
-        let mut res = base64::decode_config_slice(&chunk, base64::STANDARD, &mut buf);
+        let mut res = base64::decode_config_slice(chunk, base64::STANDARD, &mut buf);
         if let Err(base64::DecodeError::InvalidLength) = res {
             let mut v = Vec::new();
-            v.extend_from_slice(&chunk);
+            v.extend_from_slice(chunk);
             v.push(b'A');
             res = base64::decode_config_slice(v.as_slice(), base64::STANDARD, &mut buf);
         }
@@ -418,8 +525,8 @@ fn take_flip_marker_action_censor(input: &[u8]) -> Res<&[u8], FlipAction> {
 }
 
 fn parse_markers2_content(input: &[u8]) -> Res<&[u8], Markers2Content> {
-    let (input, version) = take_version(&input)?;
-    let (input, markers) = nom::multi::many0(take_marker)(&input)?;
+    let (input, version) = take_version(input)?;
+    let (input, markers) = nom::multi::many0(take_marker)(input)?;
 
     Ok((input, Markers2Content { version, markers }))
 }
@@ -433,10 +540,10 @@ fn take_nullbytes(input: &[u8]) -> Res<&[u8], &[u8]> {
 
 fn take_markers2(input: &[u8]) -> Res<&[u8], Markers2> {
     let size = input.len();
-    let (input, version) = take_version(&input)?;
+    let (input, version) = take_version(input)?;
     let version = Some(version);
-    let (input, base64_chunks) = take_base64_chunks(&input)?;
-    let (input, _) = take_nullbytes(&input)?;
+    let (input, base64_chunks) = take_base64_chunks(input)?;
+    let (input, _) = take_nullbytes(input)?;
     let base64_decoded = decode_base64_chunks(base64_chunks)?;
     let markers2_result = parse_markers2_content(&base64_decoded);
     if markers2_result.is_err() {
@@ -458,13 +565,14 @@ fn write_markers2(mut writer: impl io::Write, markers2: &Markers2) -> Result<usi
             return Err(Error::ParseError);
         }
     };
-    let mut bytes_written = write_version(&mut writer, &version)?;
+    let mut bytes_written = write_version(&mut writer, version)?;
     let mut buffer = Cursor::new(vec![]);
     write_markers2_content(&mut buffer, &markers2.content)?;
     let plain_data = &buffer.get_ref()[..];
     bytes_written += enveloped::base64_encode(&mut writer, plain_data)?;
-    if markers2.size > bytes_written {
-        for _ in 0..(markers2.size - bytes_written) {
+    let size = markers2.size.max(MARKERS2_MIN_SIZE);
+    if size > bytes_written {
+        for _ in 0..(size - bytes_written) {
             bytes_written += writer.write(b"\x00")?;
         }
     }
@@ -477,7 +585,7 @@ fn write_markers2_content(
 ) -> Result<usize, Error> {
     let mut bytes_written = write_version(&mut writer, &content.version)?;
     for marker in &content.markers {
-        bytes_written += write_marker(&mut writer, &marker)?;
+        bytes_written += write_marker(&mut writer, marker)?;
     }
     Ok(bytes_written)
 }
@@ -533,7 +641,7 @@ fn write_color_marker(
 
 fn write_cue_marker(mut writer: impl io::Write, marker: &Cue) -> Result<usize, Error> {
     let mut bytes_written = writer.write(b"CUE\0")?;
-    let size: u32 = 13 + marker.label.as_bytes().len() as u32;
+    let size: u32 = 13 + marker.label.len() as u32;
     bytes_written += writer.write(&size.to_be_bytes())?;
     bytes_written += writer.write(b"\0")?;
     bytes_written += writer.write(&[marker.index])?;
@@ -541,14 +649,14 @@ fn write_cue_marker(mut writer: impl io::Write, marker: &Cue) -> Result<usize, E
     bytes_written += writer.write(b"\0")?;
     bytes_written += write_color(&mut writer, &marker.color)?;
     bytes_written += writer.write(b"\0\0")?;
-    bytes_written += writer.write(&marker.label.as_bytes())?;
+    bytes_written += writer.write(marker.label.as_bytes())?;
     bytes_written += writer.write(b"\0")?;
     Ok(bytes_written)
 }
 
 fn write_loop_marker(mut writer: impl io::Write, marker: &Loop) -> Result<usize, Error> {
     let mut bytes_written = writer.write(b"LOOP\0")?;
-    let size: u32 = 21 + marker.label.as_bytes().len() as u32;
+    let size: u32 = 21 + marker.label.len() as u32;
     bytes_written += writer.write(&size.to_be_bytes())?;
     bytes_written += writer.write(b"\0")?;
     bytes_written += writer.write(&[marker.index])?;
@@ -558,14 +666,14 @@ fn write_loop_marker(mut writer: impl io::Write, marker: &Loop) -> Result<usize,
     bytes_written += write_color(&mut writer, &marker.color)?;
     bytes_written += writer.write(b"\0")?;
     bytes_written += write_bool(&mut writer, marker.is_locked)?;
-    bytes_written += writer.write(&marker.label.as_bytes())?;
+    bytes_written += writer.write(marker.label.as_bytes())?;
     bytes_written += writer.write(b"\0")?;
     Ok(bytes_written)
 }
 
 fn write_flip_marker(mut writer: impl io::Write, marker: &Flip) -> Result<usize, Error> {
     let mut bytes_written = writer.write(b"FLIP\0")?;
-    let mut size: u32 = 9 + marker.label.as_bytes().len() as u32;
+    let mut size: u32 = 9 + marker.label.len() as u32;
     for action in &marker.actions {
         size += match action {
             FlipAction::Jump(_) => 21u32,
@@ -578,13 +686,13 @@ fn write_flip_marker(mut writer: impl io::Write, marker: &Flip) -> Result<usize,
     bytes_written += writer.write(b"\0")?;
     bytes_written += writer.write(&[marker.index])?;
     bytes_written += write_bool(&mut writer, marker.is_enabled)?;
-    bytes_written += writer.write(&marker.label.as_bytes())?;
+    bytes_written += writer.write(marker.label.as_bytes())?;
     bytes_written += writer.write(b"\0")?;
     bytes_written += write_bool(&mut writer, marker.is_loop)?;
     let num_actions = marker.actions.len() as u32;
     bytes_written += writer.write(&num_actions.to_be_bytes())?;
     for action in &marker.actions {
-        bytes_written = write_flip_marker_action(&mut writer, &action)?;
+        bytes_written = write_flip_marker_action(&mut writer, action)?;
     }
     Ok(bytes_written)
 }
@@ -598,14 +706,14 @@ fn write_flip_marker_action(
             let mut bytes_written = writer.write(b"\x00")?;
             let size = 16u32;
             bytes_written += writer.write(&size.to_be_bytes())?;
-            bytes_written += write_flip_marker_action_jump(&mut writer, &act)?;
+            bytes_written += write_flip_marker_action_jump(&mut writer, act)?;
             Ok(bytes_written)
         }
         FlipAction::Censor(act) => {
             let mut bytes_written = writer.write(b"\x01")?;
             let size = 24u32;
             bytes_written += writer.write(&size.to_be_bytes())?;
-            bytes_written += write_flip_marker_action_censor(&mut writer, &act)?;
+            bytes_written += write_flip_marker_action_censor(&mut writer, act)?;
             Ok(bytes_written)
         }
         FlipAction::Unknown(act) => {
@@ -636,3 +744,71 @@ fn write_flip_marker_action_censor(
     bytes_written += writer.write(&action.speed_factor.to_be_bytes())?;
     Ok(bytes_written)
 }
+
+#[test]
+fn test_markers2_new() {
+    use super::format::id3::ID3Tag;
+
+    let markers2 = Markers2::new(Color::from(0xFFFFFF));
+    let mut output = Vec::new();
+    markers2.write_id3(&mut output).unwrap();
+    assert_eq!(
+        output,
+        include_bytes!("../../tests/data/tags/markers2/analyzed.id3.bin").to_vec()
+    );
+}
+
+#[test]
+fn test_markers2_marker_order() {
+    let mut markers2 = Markers2::new(Color::from(0xFFFFFF));
+    markers2
+        .content
+        .markers
+        .push(Marker::Unknown(UnknownMarker {
+            name: String::from("UNKNOWN"),
+            data: vec![],
+        }));
+    let cue = |index| Cue {
+        index,
+        position_millis: 0,
+        color: Color::from(0xCC0000),
+        label: String::new(),
+    };
+    markers2.set_cue(cue(3));
+    markers2.set_cue(cue(1));
+    markers2.set_loop(Loop {
+        index: 0,
+        start_position_millis: 0,
+        end_position_millis: 1000,
+        color: Color::from(0x27AAE1),
+        is_locked: false,
+        label: String::new(),
+    });
+    markers2.set_cue(Cue {
+        label: String::from("Replaced"),
+        ..cue(3)
+    });
+    markers2.set_bpm_locked(true);
+
+    let names: Vec<String> = markers2
+        .content
+        .markers
+        .iter()
+        .map(|marker| match marker {
+            Marker::Color(_) => String::from("COLOR"),
+            Marker::Cue(c) => format!("CUE{}", c.index),
+            Marker::Loop(l) => format!("LOOP{}", l.index),
+            Marker::BPMLock(_) => String::from("BPMLOCK"),
+            Marker::Flip(f) => format!("FLIP{}", f.index),
+            Marker::Unknown(m) => m.name.clone(),
+        })
+        .collect();
+    assert_eq!(
+        names,
+        vec!["COLOR", "CUE1", "CUE3", "LOOP0", "BPMLOCK", "UNKNOWN"]
+    );
+    assert_eq!(markers2.cues()[1].label, "Replaced");
+    assert_eq!(markers2.bpm_locked(), Some(true));
+    assert_eq!(markers2.remove_cue(1).map(|c| c.index), Some(1));
+    assert!(markers2.remove_cue(1).is_none());
+}
@@ -1,9 +1,10 @@
 //! Parsers for Serato's file tags
 pub mod color;
+pub mod file;
 pub mod format;
 pub mod generic;
 pub mod serato32;
-pub(self) mod util;
+mod util;
 
 pub mod container;
 pub use container::TagContainer;
@@ -24,11 +25,17 @@ pub use self::markers::Markers;
 pub mod markers2;
 pub use self::markers2::Markers2;
 
+pub mod offsets;
+pub use self::offsets::Offsets;
+
 pub mod overview;
 pub use self::overview::Overview;
 
 pub mod relvolad;
 pub use self::relvolad::RelVolAd;
 
+pub mod seratotag;
+pub use self::seratotag::SeratoTag;
+
 pub mod vidassoc;
 pub use self::vidassoc::VidAssoc;
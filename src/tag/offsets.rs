@@ -0,0 +1,79 @@
+//! The `Serato Offsets_` tag (not reverse-engineered yet).
+//!
+//! This tag is only written to MP3 files.
+
+use super::format::{id3, Tag};
+use super::generic::Version;
+use super::util::{take_version, write_version};
+use crate::error::Error;
+use crate::util::Res;
+use std::io;
+
+/// Represents the  `Serato Offsets_` tag.
+///
+/// **Note:** This tag has not been reverse-engineered yet. The data is kept as-is, so that parsing
+/// and writing the tag is lossless.
+///
+/// # Example
+///
+/// ```
+/// use triseratops::tag::{Offsets, format::id3::ID3Tag};
+///
+/// // First, read the tag data from the ID3 GEOB tag (the tag name can be accessed using the
+/// // Offsets::ID3_TAG), then parse the data like this:
+/// fn parse(data: &[u8]) {
+///     let content = Offsets::parse_id3(data).expect("Failed to parse data!");
+///     println!("{:?}", content);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Offsets {
+    /// The `Offsets_` version.
+    pub version: Version,
+    /// The data (not reverse-engineered yet)
+    pub data: Vec<u8>,
+}
+
+impl Tag for Offsets {
+    const NAME: &'static str = "Serato Offsets_";
+
+    fn parse(input: &[u8]) -> Result<Self, Error> {
+        let (_, offsets) = nom::combinator::all_consuming(take_offsets)(input)?;
+        Ok(offsets)
+    }
+
+    fn write(&self, writer: impl io::Write) -> Result<usize, Error> {
+        write_offsets(writer, self)
+    }
+}
+
+impl id3::ID3Tag for Offsets {}
+
+fn take_offsets(input: &[u8]) -> Res<&[u8], Offsets> {
+    let (input, version) = take_version(input)?;
+    let (input, data) = nom::combinator::rest(input)?;
+    let data = data.to_vec();
+
+    let offsets = Offsets { version, data };
+    Ok((input, offsets))
+}
+
+fn write_offsets(mut writer: impl io::Write, offsets: &Offsets) -> Result<usize, Error> {
+    let mut bytes_written = write_version(&mut writer, &offsets.version)?;
+    bytes_written += writer.write(offsets.data.as_slice())?;
+    Ok(bytes_written)
+}
+
+#[test]
+fn test_offsets_roundtrip() {
+    let input = [0x01, 0x02, 0x00, 0x00, 0x00, 0x02, 0xAB, 0xCD, 0xEF, 0x00];
+    let offsets = Offsets::parse(&input).unwrap();
+    assert_eq!(offsets.version.major, 1);
+    assert_eq!(offsets.version.minor, 2);
+    assert_eq!(offsets.data, &input[2..]);
+
+    let mut output = Vec::new();
+    let bytes_written = offsets.write(&mut output).unwrap();
+    assert_eq!(bytes_written, input.len());
+    assert_eq!(output, input);
+}
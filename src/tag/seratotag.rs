@@ -0,0 +1,192 @@
+//! Provides the [`SeratoTag` enum](SeratoTag), which allows parsing arbitrary tag data by name.
+//!
+//! Each tag format stores the Serato tags under a different name (e.g. the ID3 `GEOB` description
+//! `Serato Markers2`, the FLAC comment `SERATO_MARKERS_V2`, the MP4 freeform atom `markersv2` or
+//! the Ogg comment `serato_markers2`). [`SeratoTag::parse`] uses that name to select the matching
+//! parser.
+//!
+//! ```
+//! use triseratops::tag::{SeratoTag, TagFormat};
+//!
+//! fn parse_and_print(name: &str, data: &[u8]) {
+//!     match SeratoTag::parse(name, data, TagFormat::ID3).expect("Failed to parse data!") {
+//!         SeratoTag::Unknown(tag) => println!("Unknown tag: {}", tag.name),
+//!         tag => println!("{:?}", tag),
+//!     }
+//! }
+//! ```
+
+use super::format::{flac::FLACTag, id3::ID3Tag, mp4::MP4Tag, ogg::OggTag, Tag};
+use super::{
+    Analysis, Autotags, Beatgrid, Markers, Markers2, Offsets, Overview, RelVolAd, TagFormat,
+    VidAssoc,
+};
+use crate::error::Error;
+use std::io;
+
+/// A tag that is not known to this library.
+#[derive(Debug)]
+pub struct UnknownTag {
+    /// The name that the tag data was stored under.
+    pub name: String,
+    /// The raw tag data.
+    pub data: Vec<u8>,
+}
+
+/// Any Serato tag.
+#[derive(Debug)]
+pub enum SeratoTag {
+    Analysis(Analysis),
+    Autotags(Autotags),
+    Beatgrid(Beatgrid),
+    Markers(Markers),
+    Markers2(Markers2),
+    Offsets(Offsets),
+    Overview(Overview),
+    RelVolAd(RelVolAd),
+    VidAssoc(VidAssoc),
+    Unknown(UnknownTag),
+}
+
+impl SeratoTag {
+    /// Parse the tag data that was stored under `name`.
+    ///
+    /// The name is the ID3 `GEOB` description, the FLAC or Ogg comment name or the MP4 freeform
+    /// atom name, depending on `tag_format`. Comment names are matched case-insensitively. If the
+    /// name is not known (or the tag is not supported in that tag format), the raw data is returned
+    /// as [`SeratoTag::Unknown`].
+    pub fn parse(name: &str, input: &[u8], tag_format: TagFormat) -> Result<Self, Error> {
+        let tag = match tag_format {
+            TagFormat::ID3 => match name {
+                Analysis::ID3_TAG => Self::Analysis(Analysis::parse_id3(input)?),
+                Autotags::ID3_TAG => Self::Autotags(Autotags::parse_id3(input)?),
+                Beatgrid::ID3_TAG => Self::Beatgrid(Beatgrid::parse_id3(input)?),
+                Markers::ID3_TAG => Self::Markers(Markers::parse_id3(input)?),
+                Markers2::ID3_TAG => Self::Markers2(Markers2::parse_id3(input)?),
+                Offsets::ID3_TAG => Self::Offsets(Offsets::parse_id3(input)?),
+                Overview::ID3_TAG => Self::Overview(Overview::parse_id3(input)?),
+                _ => Self::unknown(name, input),
+            },
+            TagFormat::FLAC => match name.to_ascii_uppercase().as_str() {
+                Analysis::FLAC_COMMENT => Self::Analysis(Analysis::parse_flac(input)?),
+                Autotags::FLAC_COMMENT => Self::Autotags(Autotags::parse_flac(input)?),
+                Beatgrid::FLAC_COMMENT => Self::Beatgrid(Beatgrid::parse_flac(input)?),
+                Markers2::FLAC_COMMENT => Self::Markers2(Markers2::parse_flac(input)?),
+                Overview::FLAC_COMMENT => Self::Overview(Overview::parse_flac(input)?),
+                RelVolAd::FLAC_COMMENT => Self::RelVolAd(RelVolAd::parse_flac(input)?),
+                VidAssoc::FLAC_COMMENT => Self::VidAssoc(VidAssoc::parse_flac(input)?),
+                _ => Self::unknown(name, input),
+            },
+            TagFormat::MP4 => match name {
+                Analysis::MP4_ATOM_FREEFORM_NAME => Self::Analysis(Analysis::parse_mp4(input)?),
+                Autotags::MP4_ATOM_FREEFORM_NAME => Self::Autotags(Autotags::parse_mp4(input)?),
+                Beatgrid::MP4_ATOM_FREEFORM_NAME => Self::Beatgrid(Beatgrid::parse_mp4(input)?),
+                Markers::MP4_ATOM_FREEFORM_NAME => Self::Markers(Markers::parse_mp4(input)?),
+                Markers2::MP4_ATOM_FREEFORM_NAME => Self::Markers2(Markers2::parse_mp4(input)?),
+                Overview::MP4_ATOM_FREEFORM_NAME => Self::Overview(Overview::parse_mp4(input)?),
+                RelVolAd::MP4_ATOM_FREEFORM_NAME => Self::RelVolAd(RelVolAd::parse_mp4(input)?),
+                VidAssoc::MP4_ATOM_FREEFORM_NAME => Self::VidAssoc(VidAssoc::parse_mp4(input)?),
+                _ => Self::unknown(name, input),
+            },
+            TagFormat::Ogg => match name.to_ascii_lowercase().as_str() {
+                Analysis::OGG_COMMENT => Self::Analysis(Analysis::parse_ogg(input)?),
+                Markers2::OGG_COMMENT => Self::Markers2(Markers2::parse_ogg(input)?),
+                _ => Self::unknown(name, input),
+            },
+        };
+        Ok(tag)
+    }
+
+    fn unknown(name: &str, input: &[u8]) -> Self {
+        Self::Unknown(UnknownTag {
+            name: name.to_owned(),
+            data: input.to_vec(),
+        })
+    }
+
+    /// Returns the tag name (e.g. `Serato Markers2`), or the name that an unknown tag was stored
+    /// under.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Analysis(_) => Analysis::NAME,
+            Self::Autotags(_) => Autotags::NAME,
+            Self::Beatgrid(_) => Beatgrid::NAME,
+            Self::Markers(_) => Markers::NAME,
+            Self::Markers2(_) => Markers2::NAME,
+            Self::Offsets(_) => Offsets::NAME,
+            Self::Overview(_) => Overview::NAME,
+            Self::RelVolAd(_) => RelVolAd::NAME,
+            Self::VidAssoc(_) => VidAssoc::NAME,
+            Self::Unknown(tag) => &tag.name,
+        }
+    }
+
+    /// Serialize the tag data in the given tag format.
+    ///
+    /// Unknown tags are written as-is. Returns [`Error::UnsupportedTagFormat`] if the tag can't be
+    /// stored in that tag format.
+    pub fn write(&self, mut writer: impl io::Write, tag_format: TagFormat) -> Result<usize, Error> {
+        match (self, tag_format) {
+            (Self::Analysis(tag), TagFormat::ID3) => tag.write_id3(writer),
+            (Self::Analysis(tag), TagFormat::FLAC) => tag.write_flac(writer),
+            (Self::Analysis(tag), TagFormat::MP4) => tag.write_mp4(writer),
+            (Self::Analysis(tag), TagFormat::Ogg) => tag.write_ogg(writer),
+            (Self::Autotags(tag), TagFormat::ID3) => tag.write_id3(writer),
+            (Self::Autotags(tag), TagFormat::FLAC) => tag.write_flac(writer),
+            (Self::Autotags(tag), TagFormat::MP4) => tag.write_mp4(writer),
+            (Self::Beatgrid(tag), TagFormat::ID3) => tag.write_id3(writer),
+            (Self::Beatgrid(tag), TagFormat::FLAC) => tag.write_flac(writer),
+            (Self::Beatgrid(tag), TagFormat::MP4) => tag.write_mp4(writer),
+            (Self::Markers(tag), TagFormat::ID3) => tag.write_id3(writer),
+            (Self::Markers(tag), TagFormat::MP4) => tag.write_mp4(writer),
+            (Self::Markers2(tag), TagFormat::ID3) => tag.write_id3(writer),
+            (Self::Markers2(tag), TagFormat::FLAC) => tag.write_flac(writer),
+            (Self::Markers2(tag), TagFormat::MP4) => tag.write_mp4(writer),
+            (Self::Markers2(tag), TagFormat::Ogg) => tag.write_ogg(writer),
+            (Self::Offsets(tag), TagFormat::ID3) => tag.write_id3(writer),
+            (Self::Overview(tag), TagFormat::ID3) => tag.write_id3(writer),
+            (Self::Overview(tag), TagFormat::FLAC) => tag.write_flac(writer),
+            (Self::Overview(tag), TagFormat::MP4) => tag.write_mp4(writer),
+            (Self::RelVolAd(tag), TagFormat::FLAC) => tag.write_flac(writer),
+            (Self::RelVolAd(tag), TagFormat::MP4) => tag.write_mp4(writer),
+            (Self::VidAssoc(tag), TagFormat::FLAC) => tag.write_flac(writer),
+            (Self::VidAssoc(tag), TagFormat::MP4) => tag.write_mp4(writer),
+            (Self::Unknown(tag), _) => Ok(writer.write(&tag.data)?),
+            _ => Err(Error::UnsupportedTagFormat),
+        }
+    }
+}
+
+#[test]
+fn test_parse_unknown() {
+    let tag = SeratoTag::parse("Serato Unknown", b"\x01\x02", TagFormat::ID3).unwrap();
+    assert_eq!(tag.name(), "Serato Unknown");
+    assert!(matches!(&tag, SeratoTag::Unknown(t) if t.data == b"\x01\x02"));
+
+    // Markers_ can't be stored in Ogg files
+    let tag = SeratoTag::parse("serato_markers_", b"\x01\x02", TagFormat::Ogg).unwrap();
+    assert!(matches!(tag, SeratoTag::Unknown(_)));
+
+    let mut output = Vec::new();
+    tag.write(&mut output, TagFormat::Ogg).unwrap();
+    assert_eq!(output, b"\x01\x02");
+}
+
+#[test]
+fn test_parse_by_name() {
+    let tag = SeratoTag::parse("Serato Analysis", b"\x02\x01", TagFormat::ID3).unwrap();
+    assert!(matches!(tag, SeratoTag::Analysis(_)));
+    assert_eq!(tag.name(), "Serato Analysis");
+    assert!(matches!(tag.write(Vec::new(), TagFormat::Ogg), Ok(3)));
+
+    let tag = SeratoTag::parse("SERATO_ANALYSIS_VER", b"2.1", TagFormat::Ogg).unwrap();
+    assert!(matches!(tag, SeratoTag::Analysis(_)));
+
+    let tag = SeratoTag::parse("Serato Offsets_", b"\x01\x02\x03", TagFormat::ID3).unwrap();
+    assert!(matches!(
+        tag.write(Vec::new(), TagFormat::FLAC),
+        Err(Error::UnsupportedTagFormat)
+    ));
+
+    assert!(SeratoTag::parse("Serato Analysis", b"\x02", TagFormat::ID3).is_err());
+}
@@ -0,0 +1,82 @@
+//! Helpers that are shared between the tag file tests.
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use triseratops::tag::{SeratoTag, TagContainer, TagFormat};
+
+/// A file in a temporary directory that is removed when the value is dropped.
+pub struct ScratchFile {
+    _dir: TempDir,
+    path: PathBuf,
+}
+
+impl Deref for ScratchFile {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for ScratchFile {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Write `content` to a new file called `name` inside a temporary directory.
+pub fn scratch_file(name: &str, content: &[u8]) -> ScratchFile {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(name);
+    fs::write(&path, content).unwrap();
+    ScratchFile { _dir: dir, path }
+}
+
+/// Returns a container with the given `(name, filename)` tags parsed directly from the test data.
+pub fn expected_container(tags: &[(&str, &str)], tag_format: TagFormat) -> TagContainer {
+    let mut container = TagContainer::new();
+    for (name, filename) in tags {
+        let data = fs::read(filename).unwrap();
+        let result = match SeratoTag::parse(name, &data, tag_format).unwrap() {
+            SeratoTag::Analysis(_) => container.parse_analysis(&data, tag_format),
+            SeratoTag::Autotags(_) => container.parse_autotags(&data, tag_format),
+            SeratoTag::Beatgrid(_) => container.parse_beatgrid(&data, tag_format),
+            SeratoTag::Markers(_) => container.parse_markers(&data, tag_format),
+            SeratoTag::Markers2(_) => container.parse_markers2(&data, tag_format),
+            SeratoTag::Offsets(_) => container.parse_offsets(&data, tag_format),
+            SeratoTag::Overview(_) => container.parse_overview(&data, tag_format),
+            SeratoTag::RelVolAd(_) => container.parse_relvolad(&data, tag_format),
+            SeratoTag::VidAssoc(_) => container.parse_vidassoc(&data, tag_format),
+            tag => panic!("Tag {} is not supported by the container", tag.name()),
+        };
+        result.unwrap();
+    }
+    container
+}
+
+/// Check that both containers hold the same tag data.
+pub fn assert_container_eq(actual: &TagContainer, expected: &TagContainer) {
+    assert_eq!(actual.analysis_version(), expected.analysis_version());
+    assert_eq!(actual.auto_gain(), expected.auto_gain());
+    assert_eq!(actual.gain_db(), expected.gain_db());
+    assert_eq!(actual.beatgrid(), expected.beatgrid());
+    assert_eq!(actual.cues(), expected.cues());
+    assert_eq!(actual.loops(), expected.loops());
+    assert_eq!(actual.track_color(), expected.track_color());
+    assert_eq!(actual.bpm_locked(), expected.bpm_locked());
+    assert_eq!(actual.overview(), expected.overview());
+    assert_eq!(
+        actual.offsets().map(|tag| (&tag.version, &tag.data)),
+        expected.offsets().map(|tag| (&tag.version, &tag.data))
+    );
+    assert_eq!(
+        actual.relvolad().map(|tag| (&tag.version, &tag.data)),
+        expected.relvolad().map(|tag| (&tag.version, &tag.data))
+    );
+    assert_eq!(
+        actual.vidassoc().map(|tag| (&tag.version, &tag.data)),
+        expected.vidassoc().map(|tag| (&tag.version, &tag.data))
+    );
+}
@@ -0,0 +1,348 @@
+extern crate triseratops;
+
+use std::fs;
+use triseratops::tag::beatgrid::Quantization;
+use triseratops::tag::color::Color;
+use triseratops::tag::container::{
+    MarkersConflict, MarkersConflictKind, MarkersRepairPolicy, MarkersSlot,
+};
+use triseratops::tag::format::flac::FLACTag;
+use triseratops::tag::format::id3::ID3Tag;
+use triseratops::tag::format::ogg::OggTag;
+use triseratops::tag::generic::{Cue, Loop};
+use triseratops::tag::{Beatgrid, Markers, Markers2, RelVolAd, TagContainer, TagFormat, VidAssoc};
+
+fn cue(index: u8, position_millis: u32) -> Cue {
+    Cue {
+        index,
+        position_millis,
+        color: Color::from(0xCC0000),
+        label: format!("Cue {}", index),
+    }
+}
+
+fn saved_loop(index: u8) -> Loop {
+    Loop {
+        index,
+        start_position_millis: 1000,
+        end_position_millis: 2000,
+        color: Color::from(0x27AAE1),
+        is_locked: false,
+        label: format!("Loop {}", index),
+    }
+}
+
+/// Write the container's `Serato Markers_` and `Serato Markers2` tags and parse them again.
+fn write_and_parse(container: &TagContainer) -> (Markers, Markers2) {
+    let mut markers_data = Vec::new();
+    container
+        .write_markers(&mut markers_data, TagFormat::ID3)
+        .unwrap();
+    let mut markers2_data = Vec::new();
+    container
+        .write_markers2(&mut markers2_data, TagFormat::ID3)
+        .unwrap();
+    (
+        Markers::parse_id3(&markers_data).unwrap(),
+        Markers2::parse_id3(&markers2_data).unwrap(),
+    )
+}
+
+#[test]
+fn test_setters_create_tags() {
+    let mut container = TagContainer::new();
+    container.set_cue(cue(2, 500));
+    container.set_cue(cue(7, 1500));
+    container.set_loop(saved_loop(0));
+
+    let (markers, markers2) = write_and_parse(&container);
+    assert_eq!(markers.entries.len(), 14);
+    assert_eq!(markers.cues().len(), 5);
+    assert_eq!(markers.loops().len(), 9);
+    assert_eq!(markers.track_color(), Color::from(0xFFFFFF));
+    assert_eq!(markers.cues()[2].1.start_position_millis, Some(500));
+    assert_eq!(markers.loops()[0].1.end_position_millis, Some(2000));
+
+    assert_eq!(markers2.cues().len(), 2);
+    assert_eq!(markers2.loops().len(), 1);
+    assert_eq!(markers2.track_color(), Some(Color::from(0xFFFFFF)));
+    assert_eq!(markers2.bpm_locked(), Some(false));
+
+    let cues = container.cues();
+    assert_eq!(cues.len(), 2);
+    assert_eq!(cues[0].label, "Cue 2");
+    assert_eq!(cues[1].index, 7);
+    assert_eq!(container.loops()[0].label, "Loop 0");
+}
+
+#[test]
+fn test_setters_create_tags_ogg() {
+    let mut container = TagContainer::new();
+    container.set_cue(cue(2, 500));
+
+    let mut data = Vec::new();
+    container.write_markers2(&mut data, TagFormat::Ogg).unwrap();
+    assert!(!data.contains(&0));
+    let markers2 = Markers2::parse_ogg(&data).unwrap();
+    assert_eq!(markers2.cues().len(), 1);
+
+    // ID3 tags are still padded to the minimum size.
+    let mut data = Vec::new();
+    container.write_markers2(&mut data, TagFormat::ID3).unwrap();
+    assert_eq!(data.len(), 470);
+}
+
+#[test]
+fn test_setters_update_tags() {
+    let mut container = TagContainer::new();
+    container
+        .parse_markers(
+            include_bytes!("data/tags/markers/saved_loops.id3.bin"),
+            TagFormat::ID3,
+        )
+        .unwrap();
+
+    // `Serato Markers2` is missing and gets created from `Serato Markers_`.
+    container.set_track_color(Color::from(0x993333));
+    container.set_bpm_locked(true);
+    container.remove_loop(1);
+    container.set_cue(cue(0, 100));
+
+    let (markers, markers2) = write_and_parse(&container);
+    assert_eq!(markers.track_color(), Color::from(0x993333));
+    assert_eq!(markers2.track_color(), Some(Color::from(0x993333)));
+    assert_eq!(markers2.bpm_locked(), Some(true));
+    assert_eq!(markers.loops()[1].1.start_position_millis, None);
+    let loop_indices: Vec<u8> = markers2.loops().iter().map(|l| l.index).collect();
+    assert_eq!(loop_indices, vec![0, 2, 3]);
+    assert_eq!(markers2.cues()[0].position_millis, 100);
+
+    container.remove_cue(0);
+    assert!(container.cues().is_empty());
+    let (markers, markers2) = write_and_parse(&container);
+    assert!(markers.cues()[0].1.start_position_millis.is_none());
+    assert!(markers2.cues().is_empty());
+}
+
+#[test]
+fn test_unknown_tags() {
+    let mut container = TagContainer::new();
+    container.set_unknown_tag("SERATO_FOO", vec![0x01], TagFormat::FLAC);
+    container.set_unknown_tag("Serato Foo", vec![0x02], TagFormat::ID3);
+    container.set_unknown_tag("serato_foo", vec![0x03], TagFormat::FLAC);
+
+    // FLAC comment names are case-insensitive, so the first tag was replaced.
+    let tags: Vec<&[u8]> = container
+        .unknown_tags(TagFormat::FLAC)
+        .map(|tag| tag.data.as_slice())
+        .collect();
+    assert_eq!(tags, vec![&[0x03][..]]);
+    assert_eq!(container.unknown_tag("serato foo", TagFormat::ID3), None);
+
+    let tag = container
+        .remove_unknown_tag("Serato Foo", TagFormat::ID3)
+        .unwrap();
+    assert_eq!(tag.data, vec![0x02]);
+    assert_eq!(container.unknown_tags(TagFormat::ID3).count(), 0);
+}
+
+#[test]
+fn test_relvolad_and_vidassoc() {
+    let mut container = TagContainer::new();
+    assert!(container.relvolad().is_none());
+    assert!(container.vidassoc().is_none());
+
+    let data = fs::read("tests/data/tags/relvolad/analyzed_enc_AA.flac.bin").unwrap();
+    container.parse_relvolad(&data, TagFormat::FLAC).unwrap();
+    let expected = RelVolAd::parse_flac(&data).unwrap();
+    let relvolad = container.relvolad().unwrap();
+    assert_eq!(relvolad.version, expected.version);
+    assert_eq!(relvolad.data, expected.data);
+
+    let data = fs::read("tests/data/tags/vidassoc/analyzed_enc_AA.flac.bin").unwrap();
+    container.parse_vidassoc(&data, TagFormat::FLAC).unwrap();
+    let expected = VidAssoc::parse_flac(&data).unwrap();
+    let vidassoc = container.vidassoc().unwrap();
+    assert_eq!(vidassoc.version, expected.version);
+    assert_eq!(vidassoc.data, expected.data);
+}
+
+#[test]
+fn test_quantize() {
+    let mut container = TagContainer::new();
+    container.set_cue(cue(0, 740));
+    container.set_cue(cue(1, 1000));
+    container.set_loop(Loop {
+        start_position_millis: 1100,
+        end_position_millis: 1900,
+        ..saved_loop(0)
+    });
+    container.set_loop(Loop {
+        start_position_millis: 1100,
+        end_position_millis: 1200,
+        ..saved_loop(1)
+    });
+    assert!(container.quantized(Quantization::Beat).is_err());
+
+    let mut data = Vec::new();
+    Beatgrid::new(0.0, 0.0).write_id3(&mut data).unwrap();
+    container.parse_beatgrid(&data, TagFormat::ID3).unwrap();
+    assert!(container.quantized(Quantization::Beat).is_err());
+
+    // 120 BPM, i.e. one beat every 500 ms and one bar every 2 seconds
+    let mut data = Vec::new();
+    Beatgrid::new(0.0, 120.0).write_id3(&mut data).unwrap();
+    container.parse_beatgrid(&data, TagFormat::ID3).unwrap();
+
+    let report = container.quantized(Quantization::Bar).unwrap();
+    assert_eq!(report.cues.len(), 2);
+    assert_eq!(report.cues[0].cue.position_millis, 0);
+    assert_eq!(report.cues[0].offset_millis, -740);
+    // Snapping both ends to the same downbeat would collapse the loop
+    let saved_loop = &report.loops[1].saved_loop;
+    assert_eq!(saved_loop.start_position_millis, 2000);
+    assert_eq!(saved_loop.end_position_millis, 2100);
+
+    let report = container.quantize(Quantization::Beat).unwrap();
+    assert_eq!(report.cues.len(), 1);
+    assert_eq!(report.cues[0].offset_millis, -240);
+    assert_eq!(report.loops.len(), 2);
+    assert_eq!(report.loops[0].start_offset_millis, -100);
+    assert_eq!(report.loops[0].end_offset_millis, 100);
+
+    let (markers, markers2) = write_and_parse(&container);
+    assert_eq!(markers.cues()[0].1.start_position_millis, Some(500));
+    assert_eq!(markers2.cues()[0].position_millis, 500);
+    assert_eq!(markers2.loops()[0].end_position_millis, 2000);
+    assert_eq!(markers2.loops()[1].start_position_millis, 1000);
+    assert_eq!(markers2.loops()[1].end_position_millis, 1100);
+
+    // Quantizing again doesn't move anything
+    let report = container.quantize(Quantization::Beat).unwrap();
+    assert!(report.cues.is_empty());
+    assert!(report.loops.is_empty());
+}
+
+#[test]
+fn test_quantize_keeps_loop_color() {
+    let mut container = TagContainer::new();
+    container.set_loop(Loop {
+        start_position_millis: 1010,
+        end_position_millis: 1990,
+        color: Color::from(0xFF0000),
+        ..saved_loop(0)
+    });
+
+    // 120 BPM, i.e. one beat every 500 ms
+    let mut data = Vec::new();
+    Beatgrid::new(0.0, 120.0).write_id3(&mut data).unwrap();
+    container.parse_beatgrid(&data, TagFormat::ID3).unwrap();
+
+    let report = container.quantize(Quantization::Beat).unwrap();
+    assert_eq!(report.loops.len(), 1);
+    assert_eq!(report.loops[0].saved_loop.color, Color::from(0xFF0000));
+
+    let (markers, markers2) = write_and_parse(&container);
+    assert_eq!(markers.loops()[0].1.start_position_millis, Some(1000));
+    assert_eq!(markers.loops()[0].1.color, Color::from(0x27AAE1));
+    assert_eq!(markers2.loops()[0].start_position_millis, 1000);
+    assert_eq!(markers2.loops()[0].end_position_millis, 2000);
+    assert_eq!(markers2.loops()[0].color, Color::from(0xFF0000));
+}
+
+/// Returns a container with `Serato Markers_` and `Serato Markers2` tags that contradict each
+/// other.
+fn inconsistent_container() -> TagContainer {
+    let mut markers_source = TagContainer::new();
+    markers_source.set_track_color(Color::from(0x993333));
+    markers_source.set_cue(cue(0, 100));
+    markers_source.set_cue(cue(1, 200));
+    markers_source.set_loop(saved_loop(0));
+
+    let mut markers2_source = TagContainer::new();
+    markers2_source.set_cue(cue(0, 150));
+    markers2_source.set_cue(cue(2, 300));
+    markers2_source.set_loop(Loop {
+        is_locked: true,
+        ..saved_loop(0)
+    });
+
+    let mut markers_data = Vec::new();
+    markers_source
+        .write_markers(&mut markers_data, TagFormat::ID3)
+        .unwrap();
+    let mut markers2_data = Vec::new();
+    markers2_source
+        .write_markers2(&mut markers2_data, TagFormat::ID3)
+        .unwrap();
+
+    let mut container = TagContainer::new();
+    container
+        .parse_markers(&markers_data, TagFormat::ID3)
+        .unwrap();
+    container
+        .parse_markers2(&markers2_data, TagFormat::ID3)
+        .unwrap();
+    container
+}
+
+#[test]
+fn test_markers_conflicts() {
+    let conflict = |slot, kind| MarkersConflict { slot, kind };
+    assert_eq!(
+        inconsistent_container().markers_conflicts(),
+        vec![
+            conflict(MarkersSlot::Cue(0), MarkersConflictKind::Position),
+            conflict(MarkersSlot::Cue(1), MarkersConflictKind::MissingInMarkers2),
+            conflict(MarkersSlot::Cue(2), MarkersConflictKind::MissingInMarkers),
+            conflict(MarkersSlot::Loop(0), MarkersConflictKind::Locked),
+            conflict(MarkersSlot::TrackColor, MarkersConflictKind::Color),
+        ]
+    );
+
+    let mut container = TagContainer::new();
+    container.set_cue(cue(0, 100));
+    container.set_loop(saved_loop(3));
+    assert!(container.markers_conflicts().is_empty());
+}
+
+#[test]
+fn test_repair_markers() {
+    let mut container = inconsistent_container();
+    container.repair_markers(MarkersRepairPolicy::PreferMarkers);
+    assert!(container.markers_conflicts().is_empty());
+    let cues = container.cues();
+    assert_eq!(cues.len(), 2);
+    assert_eq!(cues[0].position_millis, 100);
+    assert_eq!(cues[0].label, "Cue 0");
+    assert_eq!(cues[1].index, 1);
+    assert!(!container.loops()[0].is_locked);
+    assert_eq!(container.track_color(), Some(Color::from(0x993333)));
+
+    let mut container = inconsistent_container();
+    container.repair_markers(MarkersRepairPolicy::PreferMarkers2);
+    assert!(container.markers_conflicts().is_empty());
+    let (markers, markers2) = write_and_parse(&container);
+    assert_eq!(markers.cues()[0].1.start_position_millis, Some(150));
+    assert_eq!(markers.cues()[1].1.start_position_millis, None);
+    assert_eq!(markers.cues()[2].1.start_position_millis, Some(300));
+    assert!(markers.loops()[0].1.is_locked);
+    assert_eq!(markers.track_color(), Color::from(0xFFFFFF));
+    assert_eq!(markers2.cues().len(), 2);
+}
+
+#[test]
+fn test_repair_markers_keeps_loop_color() {
+    let mut container = TagContainer::new();
+    container.set_loop(Loop {
+        color: Color::from(0xFF0000),
+        ..saved_loop(0)
+    });
+    assert!(container.markers_conflicts().is_empty());
+    assert_eq!(container.loops()[0].color, Color::from(0xFF0000));
+
+    container.repair_markers(MarkersRepairPolicy::PreferMarkers);
+    let (markers, markers2) = write_and_parse(&container);
+    assert_eq!(markers.loops()[0].1.color, Color::from(0x27AAE1));
+    assert_eq!(markers2.loops()[0].color, Color::from(0xFF0000));
+}
@@ -0,0 +1,2 @@
+// include tests generated by `build.rs`
+include!(concat!(env!("OUT_DIR"), "/database_tests.rs"));
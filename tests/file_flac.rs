@@ -0,0 +1,199 @@
+extern crate triseratops;
+
+mod common;
+
+use common::{assert_container_eq, expected_container, scratch_file};
+use std::fs;
+use triseratops::tag::{TagContainer, TagFormat};
+
+const AUDIO: &[u8] = b"\xFF\xF8\x69\x08\x00\x00\x00\x00AUDIO FRAMES";
+
+const COMMENTS: [(&str, &str); 7] = [
+    (
+        "SERATO_ANALYSIS",
+        "tests/data/tags/analysis/version_2_1_encoded_EA.flac.bin",
+    ),
+    (
+        "SERATO_AUTOGAIN",
+        "tests/data/tags/autotags/bpm_115_autogain_0_gaindb_0.flac.bin",
+    ),
+    (
+        "SERATO_BEATGRID",
+        "tests/data/tags/beatgrid/terminal_only_with_footer_0x00.flac.bin",
+    ),
+    (
+        "SERATO_MARKERS_V2",
+        "tests/data/tags/markers2/hotcues_and_loops.flac.bin",
+    ),
+    (
+        "SERATO_OVERVIEW",
+        "tests/data/tags/overview/analyzed_enc_EM.flac.bin",
+    ),
+    (
+        "SERATO_RELVOL",
+        "tests/data/tags/relvolad/analyzed_enc_AA.flac.bin",
+    ),
+    (
+        "SERATO_VIDASSOC",
+        "tests/data/tags/vidassoc/analyzed_enc_AA.flac.bin",
+    ),
+];
+
+fn vorbis_string(data: &[u8]) -> Vec<u8> {
+    let mut output = (data.len() as u32).to_le_bytes().to_vec();
+    output.extend_from_slice(data);
+    output
+}
+
+fn block(block_type: u8, is_last: bool, data: &[u8]) -> Vec<u8> {
+    let mut output = (data.len() as u32).to_be_bytes().to_vec();
+    output[0] = block_type | if is_last { 0x80 } else { 0x00 };
+    output.extend_from_slice(data);
+    output
+}
+
+/// Returns the content of a FLAC file with the given comments and padding length.
+fn flac_file(comments: &[(&str, Vec<u8>)], padding: Option<usize>) -> Vec<u8> {
+    let mut data = b"fLaC".to_vec();
+    data.extend(block(0x00, false, &[0x12; 34]));
+
+    let mut comment_data = vorbis_string(b"reference libFLAC 1.3.3 20190804");
+    comment_data.extend_from_slice(&((comments.len() + 1) as u32).to_le_bytes());
+    comment_data.extend(vorbis_string(b"TITLE=Test"));
+    for (name, value) in comments {
+        let mut comment = format!("{}=", name).into_bytes();
+        comment.extend_from_slice(value);
+        comment_data.extend(vorbis_string(&comment));
+    }
+    data.extend(block(0x04, padding.is_none(), &comment_data));
+
+    if let Some(length) = padding {
+        data.extend(block(0x01, true, &vec![0; length]));
+    }
+    data.extend_from_slice(AUDIO);
+    data
+}
+
+fn serato_comments() -> Vec<(&'static str, Vec<u8>)> {
+    COMMENTS
+        .iter()
+        .map(|(name, filename)| (*name, fs::read(filename).unwrap()))
+        .collect()
+}
+
+#[test]
+fn test_read_flac() {
+    let path = scratch_file("read.flac", &flac_file(&serato_comments(), Some(1024)));
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert_container_eq(&container, &expected_container(&COMMENTS, TagFormat::FLAC));
+    assert!(!container.cues().is_empty());
+}
+
+#[test]
+fn test_read_flac_invalid() {
+    let path = scratch_file("invalid.flac", b"ID3\x04\x00");
+    assert!(TagContainer::read_from_path(&path).is_err());
+}
+
+#[test]
+fn test_write_flac_into_padding() {
+    let original = flac_file(&[], Some(8192));
+    let path = scratch_file("write-padding.flac", &original);
+    expected_container(&COMMENTS, TagFormat::FLAC)
+        .write_to_path(&path)
+        .unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert_eq!(data.len(), original.len());
+    assert!(data.ends_with(AUDIO));
+    assert_container_eq(
+        &TagContainer::read_from_path(&path).unwrap(),
+        &expected_container(&COMMENTS, TagFormat::FLAC),
+    );
+}
+
+#[test]
+fn test_write_flac_without_padding() {
+    let original = flac_file(&[], None);
+    let path = scratch_file("write-no-padding.flac", &original);
+    expected_container(&COMMENTS, TagFormat::FLAC)
+        .write_to_path(&path)
+        .unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert!(data.len() > original.len() + 4096);
+    assert!(data.ends_with(AUDIO));
+    // The temporary file has been renamed over the original.
+    assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    assert_container_eq(
+        &TagContainer::read_from_path(&path).unwrap(),
+        &expected_container(&COMMENTS, TagFormat::FLAC),
+    );
+}
+
+#[test]
+fn test_write_flac_keeps_other_comments() {
+    let path = scratch_file(
+        "write-keep.flac",
+        &flac_file(&serato_comments(), Some(1024)),
+    );
+    let mut container = TagContainer::new();
+    let beatgrid =
+        fs::read("tests/data/tags/beatgrid/terminal_only_with_footer_0x41.flac.bin").unwrap();
+    container
+        .parse_beatgrid(&beatgrid, TagFormat::FLAC)
+        .unwrap();
+    container.write_to_path(&path).unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert!(data.ends_with(AUDIO));
+    let text = String::from_utf8_lossy(&data);
+    assert!(text.contains("TITLE=Test"));
+    assert!(text.contains("SERATO_ANALYSIS="));
+
+    let mut expected = expected_container(&COMMENTS, TagFormat::FLAC);
+    expected.parse_beatgrid(&beatgrid, TagFormat::FLAC).unwrap();
+    assert_container_eq(&TagContainer::read_from_path(&path).unwrap(), &expected);
+}
+
+#[test]
+fn test_write_flac_without_comment_block() {
+    let mut original = b"fLaC".to_vec();
+    original.extend(block(0x00, true, &[0x12; 34]));
+    original.extend_from_slice(AUDIO);
+    let path = scratch_file("write-no-comments.flac", &original);
+    expected_container(&COMMENTS, TagFormat::FLAC)
+        .write_to_path(&path)
+        .unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert_eq!(data[4], 0x00);
+    assert!(data.ends_with(AUDIO));
+    assert_container_eq(
+        &TagContainer::read_from_path(&path).unwrap(),
+        &expected_container(&COMMENTS, TagFormat::FLAC),
+    );
+}
+
+#[test]
+fn test_write_flac_unknown_comments() {
+    let mut comments = serato_comments();
+    comments.push(("SERATO_UNKNOWN", b"abc=".to_vec()));
+    let path = scratch_file("read-unknown.flac", &flac_file(&comments, Some(1024)));
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert_eq!(
+        container.unknown_tag("serato_unknown", TagFormat::FLAC),
+        Some(&b"abc="[..])
+    );
+    assert_eq!(container.unknown_tag("TITLE", TagFormat::FLAC), None);
+
+    let path = scratch_file("write-unknown.flac", &flac_file(&[], None));
+    container.write_to_path(&path).unwrap();
+    let data = fs::read(&path).unwrap();
+    let text = String::from_utf8_lossy(&data);
+    assert_eq!(text.matches("SERATO_UNKNOWN=abc=").count(), 1);
+    assert_container_eq(
+        &TagContainer::read_from_path(&path).unwrap(),
+        &expected_container(&COMMENTS, TagFormat::FLAC),
+    );
+}
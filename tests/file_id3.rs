@@ -0,0 +1,159 @@
+#![cfg(feature = "id3")]
+extern crate id3;
+extern crate triseratops;
+
+mod common;
+
+use common::{assert_container_eq, expected_container, scratch_file};
+use std::fs;
+use triseratops::error::Error;
+use triseratops::tag::{TagContainer, TagFormat};
+
+const TAGS: [(&str, &str); 6] = [
+    (
+        "Serato Analysis",
+        "tests/data/tags/analysis/analyzed.id3.bin",
+    ),
+    (
+        "Serato Autotags",
+        "tests/data/tags/autotags/analyzed.id3.bin",
+    ),
+    (
+        "Serato BeatGrid",
+        "tests/data/tags/beatgrid/terminal_only_with_footer_0x37.id3.bin",
+    ),
+    (
+        "Serato Markers_",
+        "tests/data/tags/markers/analyzed.id3.bin",
+    ),
+    (
+        "Serato Markers2",
+        "tests/data/tags/markers2/analyzed.id3.bin",
+    ),
+    (
+        "Serato Overview",
+        "tests/data/tags/overview/analyzed.id3.bin",
+    ),
+];
+
+/// Returns an ID3 tag that contains `GEOB` frames for all tags in `TAGS`.
+fn serato_id3_tag() -> id3::Tag {
+    let mut tag = id3::Tag::new();
+    tag.set_title("Test");
+    for (description, filename) in TAGS.iter() {
+        tag.add_encapsulated_object(
+            *description,
+            "application/octet-stream",
+            "",
+            fs::read(filename).unwrap(),
+        );
+    }
+    tag
+}
+
+#[test]
+fn test_read_mp3() {
+    let path = scratch_file("read.mp3", &[0xFF, 0xFB, 0x90, 0x00]);
+    serato_id3_tag()
+        .write_to_path(&path, id3::Version::Id3v24)
+        .unwrap();
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert_container_eq(&container, &expected_container(&TAGS, TagFormat::ID3));
+    assert!(container.auto_gain().is_some());
+    assert!(container.beatgrid().is_some());
+    assert!(container.overview().is_some());
+}
+
+#[test]
+fn test_read_mp3_unknown_tags() {
+    let path = scratch_file("read-unknown.mp3", &[0xFF, 0xFB, 0x90, 0x00]);
+    let mut tag = serato_id3_tag();
+    tag.add_encapsulated_object(
+        "Serato Unknown",
+        "application/octet-stream",
+        "",
+        vec![0x01, 0x02, 0xAB],
+    );
+    tag.add_encapsulated_object("Other", "application/octet-stream", "", vec![0x00]);
+    tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert_eq!(
+        container.unknown_tag("Serato Unknown", TagFormat::ID3),
+        Some(&[0x01, 0x02, 0xAB][..])
+    );
+    assert_eq!(container.unknown_tag("Other", TagFormat::ID3), None);
+    assert_eq!(container.unknown_tags(TagFormat::ID3).count(), 1);
+    assert_eq!(container.unknown_tags(TagFormat::FLAC).count(), 0);
+}
+
+#[test]
+fn test_read_mp3_offsets() {
+    let data = vec![0x01, 0x02, 0xAB, 0xCD];
+    let path = scratch_file("read-offsets.mp3", &[0xFF, 0xFB, 0x90, 0x00]);
+    let mut tag = id3::Tag::new();
+    tag.add_encapsulated_object(
+        "Serato Offsets_",
+        "application/octet-stream",
+        "",
+        data.clone(),
+    );
+    tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+
+    let container = TagContainer::read_from_path(&path).unwrap();
+    let offsets = container.offsets().unwrap();
+    assert_eq!((offsets.version.major, offsets.version.minor), (1, 2));
+    assert_eq!(offsets.data, &data[2..]);
+    assert_eq!(container.unknown_tags(TagFormat::ID3).count(), 0);
+
+    let mut output = Vec::new();
+    container
+        .write_offsets(&mut output, TagFormat::ID3)
+        .unwrap();
+    assert_eq!(output, data);
+}
+
+#[test]
+fn test_read_mp3_without_tag() {
+    let path = scratch_file("untagged.mp3", &[0xFF, 0xFB, 0x90, 0x00]);
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert_eq!(container.auto_gain(), None);
+    assert_eq!(container.overview(), None);
+}
+
+#[test]
+fn test_read_aiff() {
+    let mut data = b"FORM\x00\x00\x00\x1EAIFF".to_vec();
+    data.extend_from_slice(b"COMM\x00\x00\x00\x12");
+    data.extend_from_slice(&[0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10]);
+    data.extend_from_slice(&[0x40, 0x0E, 0xAC, 0x44, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    let path = scratch_file("read.aiff", &data);
+    serato_id3_tag()
+        .write_to_aiff(&path, id3::Version::Id3v24)
+        .unwrap();
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert_container_eq(&container, &expected_container(&TAGS, TagFormat::ID3));
+}
+
+#[test]
+fn test_read_wav() {
+    let mut data = b"RIFF\x1C\x00\x00\x00WAVE".to_vec();
+    data.extend_from_slice(b"fmt \x10\x00\x00\x00");
+    data.extend_from_slice(&[0x01, 0x00, 0x02, 0x00, 0x44, 0xAC, 0x00, 0x00]);
+    data.extend_from_slice(&[0x10, 0xB1, 0x02, 0x00, 0x04, 0x00, 0x10, 0x00]);
+    let path = scratch_file("read.wav", &data);
+    serato_id3_tag()
+        .write_to_wav(&path, id3::Version::Id3v24)
+        .unwrap();
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert_container_eq(&container, &expected_container(&TAGS, TagFormat::ID3));
+}
+
+#[test]
+fn test_read_unsupported_file_type() {
+    let path = scratch_file("read.txt", b"");
+    assert!(matches!(
+        TagContainer::read_from_path(&path),
+        Err(Error::UnsupportedFileType)
+    ));
+}
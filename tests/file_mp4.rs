@@ -0,0 +1,350 @@
+extern crate triseratops;
+
+mod common;
+
+use common::{assert_container_eq, expected_container, scratch_file};
+use std::convert::TryInto;
+use std::fs;
+use triseratops::tag::{TagContainer, TagFormat};
+
+const CHUNKS: [&[u8]; 2] = [b"FIRST AUDIO CHUNK", b"SECOND AUDIO CHUNK"];
+
+const ATOMS: [(&str, &str); 8] = [
+    (
+        "analysisVersion",
+        "tests/data/tags/analysis/version_2_1_encoded_EA.mp4.bin",
+    ),
+    (
+        "autgain",
+        "tests/data/tags/autotags/bpm_115_autogain_0_gaindb_0_enc_AA.mp4.bin",
+    ),
+    (
+        "beatgrid",
+        "tests/data/tags/beatgrid/terminal_only_with_footer_0x11.mp4.bin",
+    ),
+    ("markers", "tests/data/tags/markers/analyzed.mp4.bin"),
+    (
+        "markersv2",
+        "tests/data/tags/markers2/hotcues_and_loops.mp4.bin",
+    ),
+    ("overview", "tests/data/tags/overview/analyzed.mp4.bin"),
+    ("relvol", "tests/data/tags/relvolad/analyzed.mp4.bin"),
+    (
+        "videoassociation",
+        "tests/data/tags/vidassoc/analyzed_enc_BQ.mp4.bin",
+    ),
+];
+
+fn atom(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut data = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+    data.extend_from_slice(kind);
+    data.extend_from_slice(content);
+    data
+}
+
+fn freeform(name: &str, value: &[u8]) -> Vec<u8> {
+    let mut content = atom(b"mean", b"\x00\x00\x00\x00com.serato.dj");
+    content.extend(atom(
+        b"name",
+        &[b"\x00\x00\x00\x00", name.as_bytes()].concat(),
+    ));
+    content.extend(atom(
+        b"data",
+        &[b"\x00\x00\x00\x01\x00\x00\x00\x00", value].concat(),
+    ));
+    atom(b"----", &content)
+}
+
+fn serato_atoms() -> Vec<(&'static str, Vec<u8>)> {
+    ATOMS
+        .iter()
+        .map(|(name, filename)| (*name, fs::read(filename).unwrap()))
+        .collect()
+}
+
+/// Returns the `moov` atom with the given tags and chunk offsets.
+fn moov(tags: Option<&[(&str, Vec<u8>)]>, offsets: &[u64]) -> Vec<u8> {
+    let mut stco = vec![0; 4];
+    stco.extend_from_slice(&1u32.to_be_bytes());
+    stco.extend_from_slice(&(offsets[0] as u32).to_be_bytes());
+    let mut co64 = vec![0; 4];
+    co64.extend_from_slice(&1u32.to_be_bytes());
+    co64.extend_from_slice(&offsets[1].to_be_bytes());
+
+    let trak = |table: Vec<u8>| {
+        let stbl = atom(b"stbl", &[atom(b"stsd", &[0; 8]), table].concat());
+        let minf = atom(b"minf", &stbl);
+        let mdia = atom(b"mdia", &[atom(b"mdhd", &[0; 24]), minf].concat());
+        atom(b"trak", &[atom(b"tkhd", &[0; 84]), mdia].concat())
+    };
+
+    let mut content = atom(b"mvhd", &[0; 100]);
+    content.extend(trak(atom(b"stco", &stco)));
+    content.extend(trak(atom(b"co64", &co64)));
+    if let Some(tags) = tags {
+        let mut ilst = atom(
+            b"\xA9nam",
+            &atom(b"data", b"\x00\x00\x00\x01\x00\x00\x00\x00Test"),
+        );
+        for (name, value) in tags {
+            ilst.extend(freeform(name, value));
+        }
+        let mut hdlr = vec![0; 8];
+        hdlr.extend_from_slice(b"mdirappl");
+        hdlr.extend_from_slice(&[0; 9]);
+        let meta = [vec![0; 4], atom(b"hdlr", &hdlr), atom(b"ilst", &ilst)].concat();
+        content.extend(atom(b"udta", &atom(b"meta", &meta)));
+    }
+    atom(b"moov", &content)
+}
+
+/// Returns the content of an MP4 file. If `moov_first` is set, the `moov` atom is placed before
+/// the `mdat` atom and followed by a `free` atom of length `free_length` (if any).
+fn mp4_file(
+    tags: Option<&[(&str, Vec<u8>)]>,
+    moov_first: bool,
+    free_length: Option<usize>,
+) -> Vec<u8> {
+    let ftyp = atom(b"ftyp", b"M4A \x00\x00\x02\x00M4A mp42isom");
+    let free = free_length
+        .map(|length| atom(b"free", &vec![0; length - 8]))
+        .unwrap_or_default();
+    let mdat = atom(b"mdat", &CHUNKS.concat());
+
+    let moov_length = moov(tags, &[0, 0]).len();
+    let mdat_offset = if moov_first {
+        ftyp.len() + moov_length + free.len()
+    } else {
+        ftyp.len()
+    } as u64;
+    let offsets = [mdat_offset + 8, mdat_offset + 8 + CHUNKS[0].len() as u64];
+    let moov = moov(tags, &offsets);
+
+    if moov_first {
+        [ftyp, moov, free, mdat].concat()
+    } else {
+        [ftyp, mdat, moov].concat()
+    }
+}
+
+/// Returns the chunk offsets from the `stco` and `co64` atoms.
+fn chunk_offsets(data: &[u8]) -> Vec<u64> {
+    let find = |kind: &[u8]| {
+        let pattern = [kind, b"\x00\x00\x00\x00\x00\x00\x00\x01"].concat();
+        let index = data
+            .windows(pattern.len())
+            .position(|window| window == &pattern[..])
+            .unwrap();
+        index + pattern.len()
+    };
+    let stco = find(b"stco");
+    let co64 = find(b"co64");
+    let mut stco_bytes = [0; 4];
+    stco_bytes.copy_from_slice(&data[stco..stco + 4]);
+    let mut co64_bytes = [0; 8];
+    co64_bytes.copy_from_slice(&data[co64..co64 + 8]);
+    vec![
+        u64::from(u32::from_be_bytes(stco_bytes)),
+        u64::from_be_bytes(co64_bytes),
+    ]
+}
+
+fn assert_chunks_intact(data: &[u8]) {
+    for (offset, chunk) in chunk_offsets(data).into_iter().zip(CHUNKS.iter()) {
+        let offset = offset as usize;
+        assert_eq!(&data[offset..offset + chunk.len()], *chunk);
+    }
+}
+
+#[test]
+fn test_read_mp4() {
+    let data = mp4_file(Some(&serato_atoms()), true, None);
+    assert_chunks_intact(&data);
+    let path = scratch_file("read.m4a", &data);
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert_container_eq(&container, &expected_container(&ATOMS, TagFormat::MP4));
+    assert!(!container.cues().is_empty());
+}
+
+#[test]
+fn test_read_mp4_without_tags() {
+    let path = scratch_file("read-untagged.m4a", &mp4_file(None, true, None));
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert!(container.cues().is_empty());
+    assert_eq!(container.overview(), None);
+}
+
+#[test]
+fn test_write_mp4_moov_first() {
+    let original = mp4_file(Some(&[]), true, None);
+    let path = scratch_file("write-moov-first.m4a", &original);
+    expected_container(&ATOMS, TagFormat::MP4)
+        .write_to_path(&path)
+        .unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert!(data.len() > original.len());
+    assert_chunks_intact(&data);
+    assert_ne!(chunk_offsets(&data), chunk_offsets(&original));
+    assert_container_eq(
+        &TagContainer::read_from_path(&path).unwrap(),
+        &expected_container(&ATOMS, TagFormat::MP4),
+    );
+}
+
+#[test]
+fn test_write_mp4_into_free_atom() {
+    let original = mp4_file(Some(&[]), true, Some(16384));
+    let path = scratch_file("write-free.m4a", &original);
+    expected_container(&ATOMS, TagFormat::MP4)
+        .write_to_path(&path)
+        .unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert_eq!(data.len(), original.len());
+    assert_eq!(chunk_offsets(&data), chunk_offsets(&original));
+    assert_chunks_intact(&data);
+    assert_container_eq(
+        &TagContainer::read_from_path(&path).unwrap(),
+        &expected_container(&ATOMS, TagFormat::MP4),
+    );
+}
+
+#[test]
+fn test_write_mp4_free_atom_too_small() {
+    let original = mp4_file(Some(&[]), true, Some(16));
+    let path = scratch_file("write-free-small.m4a", &original);
+    expected_container(&ATOMS, TagFormat::MP4)
+        .write_to_path(&path)
+        .unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert!(data.len() > original.len());
+    assert_chunks_intact(&data);
+    let shift = (data.len() - original.len()) as u64;
+    let shifted: Vec<u64> = chunk_offsets(&original)
+        .into_iter()
+        .map(|offset| offset + shift)
+        .collect();
+    assert_eq!(chunk_offsets(&data), shifted);
+    // The temporary file has been renamed over the original.
+    assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    assert_container_eq(
+        &TagContainer::read_from_path(&path).unwrap(),
+        &expected_container(&ATOMS, TagFormat::MP4),
+    );
+}
+
+#[test]
+fn test_write_mp4_moov_last() {
+    let original = mp4_file(None, false, None);
+    let path = scratch_file("write-moov-last.m4a", &original);
+    expected_container(&ATOMS, TagFormat::MP4)
+        .write_to_path(&path)
+        .unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert!(data.len() > original.len());
+    assert_eq!(chunk_offsets(&data), chunk_offsets(&original));
+    assert_chunks_intact(&data);
+    assert_container_eq(
+        &TagContainer::read_from_path(&path).unwrap(),
+        &expected_container(&ATOMS, TagFormat::MP4),
+    );
+}
+
+#[test]
+fn test_write_mp4_replaces_existing_tags() {
+    let original = mp4_file(Some(&serato_atoms()), true, None);
+    let path = scratch_file("write-replace.m4a", &original);
+    let mut container = TagContainer::new();
+    let beatgrid =
+        fs::read("tests/data/tags/beatgrid/terminal_only_with_footer_0xB0.mp4.bin").unwrap();
+    container.parse_beatgrid(&beatgrid, TagFormat::MP4).unwrap();
+    container.write_to_path(&path).unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert_chunks_intact(&data);
+    let text = String::from_utf8_lossy(&data);
+    assert_eq!(text.matches("beatgrid").count(), 1);
+    assert!(text.contains("analysisVersion"));
+    assert!(data.windows(4).any(|window| window == b"\xA9nam"));
+
+    let mut expected = expected_container(&ATOMS, TagFormat::MP4);
+    expected.parse_beatgrid(&beatgrid, TagFormat::MP4).unwrap();
+    assert_container_eq(&TagContainer::read_from_path(&path).unwrap(), &expected);
+
+    // Writing the same data again results in the same file
+    container.write_to_path(&path).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), data);
+}
+
+#[test]
+fn test_write_mp4_unknown_atoms() {
+    let mut atoms = serato_atoms();
+    atoms.push(("unknownTag", vec![0x01, 0x02]));
+    let path = scratch_file("read-unknown.m4a", &mp4_file(Some(&atoms), true, None));
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert_eq!(
+        container.unknown_tag("unknownTag", TagFormat::MP4),
+        Some(&[0x01, 0x02][..])
+    );
+
+    let path = scratch_file("write-unknown.m4a", &mp4_file(None, false, None));
+    container.write_to_path(&path).unwrap();
+    let data = fs::read(&path).unwrap();
+    assert_chunks_intact(&data);
+    let actual = TagContainer::read_from_path(&path).unwrap();
+    assert_container_eq(&actual, &expected_container(&ATOMS, TagFormat::MP4));
+    assert_eq!(
+        actual.unknown_tag("unknownTag", TagFormat::MP4),
+        Some(&[0x01, 0x02][..])
+    );
+}
+
+#[test]
+fn test_write_mp4_quicktime_udta_and_large_size() {
+    let large_atom = |kind: &[u8; 4], content: &[u8]| {
+        let mut data = 1u32.to_be_bytes().to_vec();
+        data.extend_from_slice(kind);
+        data.extend_from_slice(&((content.len() + 16) as u64).to_be_bytes());
+        data.extend_from_slice(content);
+        data
+    };
+
+    // QuickTime `udta` atoms may end with a 4-byte zero terminator
+    let udta = atom(
+        b"udta",
+        &[atom(b"\xA9cmt", b"\x00\x04\x00\x00Test"), vec![0; 4]].concat(),
+    );
+    let trak = atom(b"trak", &[atom(b"tkhd", &[0; 84]), udta].concat());
+    let mvhd = large_atom(b"mvhd", &[0; 100]);
+    let moov = large_atom(b"moov", &[mvhd.clone(), trak.clone()].concat());
+    let ftyp = atom(b"ftyp", b"M4A \x00\x00\x02\x00M4A mp42isom");
+    let mdat = atom(b"mdat", &CHUNKS.concat());
+    let original = [ftyp.clone(), mdat.clone(), moov].concat();
+
+    let path = scratch_file("write-quicktime-udta.m4a", &original);
+    assert!(TagContainer::read_from_path(&path)
+        .unwrap()
+        .cues()
+        .is_empty());
+    expected_container(&ATOMS, TagFormat::MP4)
+        .write_to_path(&path)
+        .unwrap();
+
+    let data = fs::read(&path).unwrap();
+    let moov_offset = ftyp.len() + mdat.len();
+    assert_eq!(&data[..moov_offset], &original[..moov_offset]);
+    assert_eq!(&data[moov_offset..moov_offset + 8], b"\x00\x00\x00\x01moov");
+    let moov_length =
+        u64::from_be_bytes(data[moov_offset + 8..moov_offset + 16].try_into().unwrap());
+    assert_eq!(moov_length as usize, data.len() - moov_offset);
+    let contains = |pattern: &[u8]| data.windows(pattern.len()).any(|window| window == pattern);
+    assert!(contains(&mvhd));
+    assert!(contains(&trak));
+    assert_container_eq(
+        &TagContainer::read_from_path(&path).unwrap(),
+        &expected_container(&ATOMS, TagFormat::MP4),
+    );
+}
@@ -0,0 +1,281 @@
+extern crate triseratops;
+
+mod common;
+
+use common::{assert_container_eq, expected_container, scratch_file};
+use std::fs;
+use triseratops::tag::{TagContainer, TagFormat};
+
+const SERIAL: u32 = 0x1234_5678;
+const AUDIO_PACKETS: [(&[u8], u64); 3] = [
+    (b"FIRST AUDIO PACKET", 1024),
+    (b"SECOND AUDIO PACKET", 2048),
+    (b"THIRD AUDIO PACKET", 3072),
+];
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for &byte in data {
+        crc ^= u32::from(byte) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[derive(Debug, PartialEq)]
+struct Page {
+    header_type: u8,
+    granule_position: u64,
+    sequence: u32,
+    segments: Vec<u8>,
+    data: Vec<u8>,
+}
+
+fn page(
+    header_type: u8,
+    granule_position: u64,
+    sequence: u32,
+    segments: &[u8],
+    data: &[u8],
+) -> Vec<u8> {
+    let mut page = b"OggS\x00".to_vec();
+    page.push(header_type);
+    page.extend_from_slice(&granule_position.to_le_bytes());
+    page.extend_from_slice(&SERIAL.to_le_bytes());
+    page.extend_from_slice(&sequence.to_le_bytes());
+    page.extend_from_slice(&[0; 4]);
+    page.push(segments.len() as u8);
+    page.extend_from_slice(segments);
+    page.extend_from_slice(data);
+    let crc = crc32(&page);
+    page[22..26].copy_from_slice(&crc.to_le_bytes());
+    page
+}
+
+/// Parses all pages and checks their CRCs.
+fn parse_pages(mut data: &[u8]) -> Vec<Page> {
+    let mut pages = Vec::new();
+    while !data.is_empty() {
+        assert_eq!(&data[..4], b"OggS");
+        let segment_count = usize::from(data[26]);
+        let segments = data[27..27 + segment_count].to_vec();
+        let length = 27 + segment_count + segments.iter().map(|&s| usize::from(s)).sum::<usize>();
+        let mut raw = data[..length].to_vec();
+        raw[22..26].copy_from_slice(&[0; 4]);
+        assert_eq!(crc32(&raw).to_le_bytes(), data[22..26]);
+
+        let mut granule_position = [0; 8];
+        granule_position.copy_from_slice(&data[6..14]);
+        let mut sequence = [0; 4];
+        sequence.copy_from_slice(&data[18..22]);
+        pages.push(Page {
+            header_type: data[5],
+            granule_position: u64::from_le_bytes(granule_position),
+            sequence: u32::from_le_bytes(sequence),
+            data: data[27 + segment_count..length].to_vec(),
+            segments,
+        });
+        data = &data[length..];
+    }
+    pages
+}
+
+fn lacing(packet: &[u8]) -> Vec<u8> {
+    let mut segments = vec![255; packet.len() / 255];
+    segments.push((packet.len() % 255) as u8);
+    segments
+}
+
+fn comment_header(magic: &[u8], comments: &[(&str, &[u8])], rest: &[u8]) -> Vec<u8> {
+    let mut packet = magic.to_vec();
+    let vendor = b"Xiph.Org libVorbis I 20200704 (Reducing Environment)";
+    packet.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    packet.extend_from_slice(vendor);
+    packet.extend_from_slice(&(comments.len() as u32 + 1).to_le_bytes());
+    let title = b"TITLE=Test";
+    packet.extend_from_slice(&(title.len() as u32).to_le_bytes());
+    packet.extend_from_slice(title);
+    for (name, value) in comments {
+        let comment = [name.as_bytes(), b"=", value].concat();
+        packet.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        packet.extend_from_slice(&comment);
+    }
+    packet.extend_from_slice(rest);
+    packet
+}
+
+/// Returns the content of an Ogg file. The header packets after the identification header are
+/// split into pages of at most `segments_per_page` segments.
+fn ogg_file(opus: bool, comments: &[(&str, &[u8])], segments_per_page: usize) -> Vec<u8> {
+    let (identification, headers) = if opus {
+        (
+            b"OpusHead\x01\x02\x38\x01\x80\xBB\x00\x00\x00\x00\x00".to_vec(),
+            vec![comment_header(b"OpusTags", comments, b"")],
+        )
+    } else {
+        (
+            [&b"\x01vorbis"[..], &[0x00; 23]].concat(),
+            vec![
+                comment_header(b"\x03vorbis", comments, b"\x01"),
+                [&b"\x05vorbis"[..], &[0x42; 700]].concat(),
+            ],
+        )
+    };
+
+    let mut data = page(0x02, 0, 0, &lacing(&identification), &identification);
+    let mut sequence = 1;
+    let segments: Vec<u8> = headers.iter().flat_map(|packet| lacing(packet)).collect();
+    let bytes: Vec<u8> = headers.concat();
+    let mut offset = 0;
+    let mut continued = false;
+    for chunk in segments.chunks(segments_per_page) {
+        let length: usize = chunk.iter().map(|&s| usize::from(s)).sum();
+        let ends_packet = chunk.iter().any(|&s| s < 255);
+        let granule_position = if ends_packet { 0 } else { u64::MAX };
+        data.extend(page(
+            if continued { 0x01 } else { 0x00 },
+            granule_position,
+            sequence,
+            chunk,
+            &bytes[offset..offset + length],
+        ));
+        continued = chunk.last() == Some(&255);
+        offset += length;
+        sequence += 1;
+    }
+    for (i, (packet, granule_position)) in AUDIO_PACKETS.iter().enumerate() {
+        let header_type = if i + 1 == AUDIO_PACKETS.len() {
+            0x04
+        } else {
+            0x00
+        };
+        data.extend(page(
+            header_type,
+            *granule_position,
+            sequence,
+            &lacing(packet),
+            packet,
+        ));
+        sequence += 1;
+    }
+    data
+}
+
+fn assert_stream_valid(data: &[u8]) {
+    let pages = parse_pages(data);
+    for (i, page) in pages.iter().enumerate() {
+        assert_eq!(page.sequence, i as u32);
+    }
+    assert_eq!(pages[0].header_type, 0x02);
+    let audio_pages = &pages[pages.len() - AUDIO_PACKETS.len()..];
+    for (page, (packet, granule_position)) in audio_pages.iter().zip(AUDIO_PACKETS.iter()) {
+        assert_eq!(&page.data, packet);
+        assert_eq!(page.granule_position, *granule_position);
+    }
+}
+
+const MARKERS2: (&str, &str) = (
+    "serato_markers2",
+    "tests/data/tags/markers2/hotcues_and_loops.ogg.bin",
+);
+
+fn markers2_data() -> Vec<u8> {
+    fs::read(MARKERS2.1).unwrap()
+}
+
+#[test]
+fn test_read_ogg_vorbis() {
+    let markers2 = markers2_data();
+    let data = ogg_file(false, &[("serato_markers2", &markers2)], 255);
+    assert_stream_valid(&data);
+    let path = scratch_file("read.ogg", &data);
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert_container_eq(&container, &expected_container(&[MARKERS2], TagFormat::Ogg));
+    assert!(!container.cues().is_empty());
+}
+
+#[test]
+fn test_read_ogg_opus() {
+    let markers2 = markers2_data();
+    let path = scratch_file(
+        "read.opus",
+        &ogg_file(true, &[("SERATO_MARKERS2", &markers2)], 255),
+    );
+    let container = TagContainer::read_from_path(&path).unwrap();
+    assert_container_eq(&container, &expected_container(&[MARKERS2], TagFormat::Ogg));
+}
+
+#[test]
+fn test_read_ogg_invalid_crc() {
+    let mut data = ogg_file(false, &[], 255);
+    data[30] ^= 0xFF;
+    let path = scratch_file("invalid-crc.ogg", &data);
+    assert!(TagContainer::read_from_path(&path).is_err());
+}
+
+#[test]
+fn test_write_ogg_vorbis() {
+    let original = ogg_file(false, &[], 255);
+    let path = scratch_file("write.ogg", &original);
+    expected_container(&[MARKERS2], TagFormat::Ogg)
+        .write_to_path(&path)
+        .unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert_stream_valid(&data);
+    assert!(String::from_utf8_lossy(&data).contains("TITLE=Test"));
+    assert_container_eq(
+        &TagContainer::read_from_path(&path).unwrap(),
+        &expected_container(&[MARKERS2], TagFormat::Ogg),
+    );
+
+    // Writing the same data again results in the same file
+    expected_container(&[MARKERS2], TagFormat::Ogg)
+        .write_to_path(&path)
+        .unwrap();
+    assert_eq!(fs::read(&path).unwrap(), data);
+
+    // The temporary file has been renamed over the original one
+    assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_write_ogg_opus() {
+    let original = ogg_file(true, &[], 255);
+    let path = scratch_file("write.opus", &original);
+    expected_container(&[MARKERS2], TagFormat::Ogg)
+        .write_to_path(&path)
+        .unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert_stream_valid(&data);
+    assert_container_eq(
+        &TagContainer::read_from_path(&path).unwrap(),
+        &expected_container(&[MARKERS2], TagFormat::Ogg),
+    );
+}
+
+#[test]
+fn test_write_ogg_renumbers_pages() {
+    let markers2 = markers2_data();
+    let original = ogg_file(false, &[("SERATO_MARKERS2", &markers2)], 1);
+    let original_pages = parse_pages(&original);
+    let path = scratch_file("write-renumber.ogg", &original);
+    let mut container = TagContainer::new();
+    let data = fs::read("tests/data/tags/markers2/track_color_993333.ogg.bin").unwrap();
+    container.parse_markers2(&data, TagFormat::Ogg).unwrap();
+    container.write_to_path(&path).unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert_stream_valid(&data);
+    assert!(parse_pages(&data).len() < original_pages.len());
+    let text = String::from_utf8_lossy(&data).to_ascii_uppercase();
+    assert_eq!(text.matches("SERATO_MARKERS2=").count(), 1);
+    assert_container_eq(&TagContainer::read_from_path(&path).unwrap(), &container);
+}
@@ -1,10 +1,40 @@
 extern crate triseratops;
 
-use triseratops::library::{Library, Track};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tempfile::TempDir;
+use triseratops::error::Error;
+use triseratops::library::database::Field;
+use triseratops::library::{Crate, CrateColumn, CrateSorting, Library, Track};
+
+const LIBRARY_PATH: &str = "tests/data/library/usb_drive";
+
+/// Copies the directory at `source` to `destination` recursively.
+fn copy_dir(source: &Path, destination: &Path) {
+    fs::create_dir_all(destination).unwrap();
+    for entry in fs::read_dir(source).unwrap() {
+        let path = entry.unwrap().path();
+        let target = destination.join(path.file_name().unwrap());
+        if path.is_dir() {
+            copy_dir(&path, &target);
+        } else {
+            fs::copy(&path, &target).unwrap();
+        }
+    }
+}
+
+/// Returns a temporary directory with a scratch copy of the test library that can be modified.
+fn scratch_library() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    copy_dir(Path::new(LIBRARY_PATH), dir.path());
+    dir
+}
 
 #[test]
 fn test_library() {
-    let library = Library::read_from_path("tests/data/library/usb_drive").unwrap();
+    let library = Library::read_from_path(LIBRARY_PATH).unwrap();
     let tracks: Vec<&Track> = library.tracks().collect();
     assert_eq!(tracks.len(), 4);
 
@@ -17,3 +47,363 @@ fn test_library() {
     let tracks: Vec<&Track> = library.subcrate("French House").unwrap().collect();
     assert_eq!(tracks.len(), 2);
 }
+
+#[test]
+fn test_library_edit_subcrates() {
+    let dir = scratch_library();
+    let path = dir.path();
+    let library = Library::read_from_path(path).unwrap();
+    let mut track_paths: Vec<PathBuf> = library.tracks().map(|t| t.file_path.clone()).collect();
+    track_paths.sort();
+
+    library.create_subcrate("New Crate").unwrap();
+    assert!(library.create_subcrate("New Crate").is_err());
+    assert_eq!(library.subcrates().count(), 3);
+    assert!(library
+        .read_subcrate("New Crate")
+        .unwrap()
+        .track_paths
+        .is_empty());
+
+    for track_path in &track_paths {
+        library.add_subcrate_track("New Crate", track_path).unwrap();
+    }
+    library
+        .add_subcrate_track("New Crate", &track_paths[0])
+        .unwrap();
+    assert_eq!(
+        library.subcrate_track_paths("New Crate").unwrap(),
+        track_paths
+    );
+    assert_eq!(library.subcrate("New Crate").unwrap().count(), 4);
+
+    library.move_subcrate_track("New Crate", 0, 3).unwrap();
+    let mut expected = track_paths.clone();
+    expected.rotate_left(1);
+    assert_eq!(
+        library.read_subcrate("New Crate").unwrap().track_paths,
+        expected
+    );
+    assert!(library.move_subcrate_track("New Crate", 0, 4).is_err());
+
+    library
+        .remove_subcrate_track("New Crate", &track_paths[1])
+        .unwrap();
+    expected.retain(|p| p != &track_paths[1]);
+    assert_eq!(
+        library.read_subcrate("New Crate").unwrap().track_paths,
+        expected
+    );
+
+    // Existing crates keep their header fields when the tracks are replaced.
+    let french_house = path.join("_Serato_/Subcrates/French House.crate");
+    let original = fs::read(&french_house).unwrap();
+    let french_house_tracks = library.read_subcrate("French House").unwrap().track_paths;
+    library
+        .set_subcrate_tracks("French House", &french_house_tracks)
+        .unwrap();
+    assert_eq!(fs::read(&french_house).unwrap(), original);
+
+    // Newly created crates use the same layout as the ones created by Serato DJ.
+    let mashup_tracks = library.read_subcrate("80s Mashup").unwrap().track_paths;
+    library.set_subcrate_tracks("Copy", &mashup_tracks).unwrap();
+    assert_eq!(
+        fs::read(path.join("_Serato_/Subcrates/Copy.crate")).unwrap(),
+        fs::read(path.join("_Serato_/Subcrates/80s Mashup.crate")).unwrap()
+    );
+    library.delete_subcrate("Copy").unwrap();
+
+    library.delete_subcrate("New Crate").unwrap();
+    assert_eq!(library.subcrates().count(), 2);
+    // No temporary files are left behind.
+    let subcrates_path = path.join("_Serato_/Subcrates");
+    assert_eq!(fs::read_dir(subcrates_path).unwrap().count(), 2);
+}
+
+#[test]
+fn test_library_invalid_subcrate_names() {
+    let dir = scratch_library();
+    let path = dir.path();
+    let library = Library::read_from_path(path).unwrap();
+    let outside = path.join("outside.crate");
+    let absolute = outside.with_extension("");
+    let absolute = absolute.to_str().unwrap();
+    for name in &["", "../../outside", "..", "a/b", "a\\b", "a\0b", absolute] {
+        let err = library.create_subcrate(name).unwrap_err();
+        assert!(matches!(err, Error::IOError(err) if err.kind() == io::ErrorKind::InvalidInput));
+        assert!(library.write_subcrate(name, &Crate::new()).is_err());
+        assert!(library.read_subcrate(name).is_err());
+        assert!(library.delete_subcrate(name).is_err());
+    }
+    assert!(!outside.exists());
+    assert_eq!(library.subcrates().count(), 2);
+
+    for name in &["Vol.. 2", "Intro..."] {
+        library.create_subcrate(name).unwrap();
+        assert!(library.read_subcrate(name).is_ok());
+        library.delete_subcrate(name).unwrap();
+    }
+}
+
+#[test]
+fn test_library_read_subcrate() {
+    let library = Library::read_from_path(LIBRARY_PATH).unwrap();
+    let krate = library.read_subcrate("French House").unwrap();
+    assert_eq!(krate.version, "1.0/Serato ScratchLive Crate");
+    assert_eq!(
+        krate.sorting,
+        Some(CrateSorting {
+            column: String::from("#"),
+            reverse: false,
+        })
+    );
+
+    let columns: Vec<(&str, u32)> = krate
+        .columns
+        .iter()
+        .map(|column| (column.name.as_str(), column.width))
+        .collect();
+    assert_eq!(
+        columns,
+        vec![
+            ("art", 0),
+            ("song", 450),
+            ("artist", 0),
+            ("bpm", 0),
+            ("key", 0),
+            ("album", 0),
+            ("length", 0),
+            ("comment", 0),
+        ]
+    );
+    assert_eq!(
+        krate.track_paths,
+        vec![
+            PathBuf::from("ALAN BRAXE - INTRO ( Max Padovani Remix).mp3"),
+            PathBuf::from("CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3"),
+        ]
+    );
+    assert!(krate.unknown_fields.is_empty());
+}
+
+#[test]
+fn test_crate_unexpected_fields() {
+    let fields = vec![
+        Field::Version(String::from("1.0/Serato ScratchLive Crate")),
+        Field::UnknownBoolean {
+            name: b"xyz".to_vec(),
+            value: true,
+        },
+        Field::Sorting(vec![
+            Field::ColumnName(String::from("#")),
+            Field::ReverseOrder(false),
+            Field::UnknownU32Field {
+                name: b"xyz".to_vec(),
+                value: 1,
+            },
+        ]),
+        Field::ColumnTitle(vec![
+            Field::ColumnName(String::from("song")),
+            Field::ColumnWidth(String::from("wide")),
+        ]),
+        Field::ColumnTitle(vec![
+            Field::ColumnName(String::from("bpm")),
+            Field::ColumnWidth(String::from("0")),
+        ]),
+        Field::Track(vec![
+            Field::UnknownU32Field {
+                name: b"xyz".to_vec(),
+                value: 2,
+            },
+            Field::TrackPath(PathBuf::from("a.mp3")),
+        ]),
+        Field::Track(vec![Field::TrackPath(PathBuf::from("b.mp3"))]),
+    ];
+
+    let mut krate = Crate::from_fields(fields.clone()).unwrap();
+    assert_eq!(krate.sorting, None);
+    assert_eq!(krate.columns.len(), 1);
+    assert_eq!(krate.unknown_fields.len(), 3);
+    assert_eq!(
+        krate.track_paths,
+        vec![PathBuf::from("a.mp3"), PathBuf::from("b.mp3")]
+    );
+    assert_eq!(krate.to_fields(), fields);
+
+    // The original field layout is not part of the comparison.
+    let mut other = Crate::new();
+    other.version = krate.version.clone();
+    other.sorting = None;
+    other.columns = krate.columns.clone();
+    other.track_paths = krate.track_paths.clone();
+    other.unknown_fields = krate.unknown_fields.clone();
+    assert_ne!(other.to_fields(), fields);
+    assert_eq!(krate, other);
+    other.track_paths.reverse();
+    assert_ne!(krate, other);
+
+    // New columns are inserted after the existing ones, new tracks are appended.
+    krate.columns.push(CrateColumn {
+        name: String::from("key"),
+        width: 0,
+    });
+    krate.track_paths.remove(0);
+    krate.track_paths.push(PathBuf::from("c.mp3"));
+    let fields = krate.to_fields();
+    assert!(
+        matches!(&fields[5], Field::ColumnTitle(f) if f[0] == Field::ColumnName(String::from("key")))
+    );
+    assert_eq!(
+        fields[6..],
+        [
+            Field::Track(vec![Field::TrackPath(PathBuf::from("b.mp3"))]),
+            Field::Track(vec![Field::TrackPath(PathBuf::from("c.mp3"))]),
+        ]
+    );
+}
+
+#[test]
+fn test_library_subcrate_tree() {
+    let dir = scratch_library();
+    let path = dir.path();
+    let library = Library::read_from_path(path).unwrap();
+
+    library.create_subcrate("House").unwrap();
+    let deep_house = library
+        .create_nested_subcrate(Some("House"), "Deep House")
+        .unwrap();
+    assert_eq!(deep_house, "House%%Deep House");
+    assert!(path
+        .join("_Serato_/Subcrates/House%%Deep House.crate")
+        .is_file());
+    assert!(library
+        .create_nested_subcrate(Some("House"), "Bad%%Name")
+        .is_err());
+    for parent in &["", "%%House", "House%%", "House%%%%Deep House"] {
+        assert!(library
+            .create_nested_subcrate(Some(parent), "Bad Parent")
+            .is_err());
+    }
+    assert!(library.move_subcrate("House", Some("")).is_err());
+
+    let french_house = library
+        .move_subcrate("French House", Some("House"))
+        .unwrap();
+    assert_eq!(french_house, "House%%French House");
+    assert_eq!(library.subcrate(&french_house).unwrap().count(), 2);
+
+    let tree = library.subcrate_tree();
+    let names: Vec<&str> = tree.iter().map(|node| node.name()).collect();
+    assert_eq!(names, vec!["80s Mashup", "House"]);
+    let house = &tree[1];
+    assert_eq!(house.depth(), 0);
+    assert_eq!(house.parent_name(), None);
+    let children: Vec<&str> = house.children.iter().map(|node| node.name()).collect();
+    assert_eq!(children, vec!["Deep House", "French House"]);
+    let french_house_node = &house.children[1];
+    assert_eq!(french_house_node.full_name, "House%%French House");
+    assert_eq!(french_house_node.depth(), 1);
+    assert_eq!(french_house_node.parent_name(), Some("House"));
+    assert_eq!(french_house_node.path(), vec!["House", "French House"]);
+
+    // Implicit parents without a crate file of their own are sorted by name, too.
+    library.create_subcrate("Rock n Roll").unwrap();
+    library.create_subcrate("Rock%%Punk").unwrap();
+    let tree = library.subcrate_tree();
+    let names: Vec<&str> = tree.iter().map(|node| node.name()).collect();
+    assert_eq!(names, vec!["80s Mashup", "House", "Rock", "Rock n Roll"]);
+    assert_eq!(tree[2].children[0].full_name, "Rock%%Punk");
+    library.delete_subcrate("Rock n Roll").unwrap();
+    library.delete_subcrate("Rock%%Punk").unwrap();
+
+    // Moving a subcrate also moves its children.
+    assert!(library
+        .move_subcrate("House", Some("House%%Deep House"))
+        .is_err());
+    let house = library.move_subcrate("House", Some("80s Mashup")).unwrap();
+    assert_eq!(house, "80s Mashup%%House");
+    let mut names: Vec<String> = library.subcrates().collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "80s Mashup",
+            "80s Mashup%%House",
+            "80s Mashup%%House%%Deep House",
+            "80s Mashup%%House%%French House",
+        ]
+    );
+    let tree = library.subcrate_tree();
+    let all: Vec<&str> = tree[0].iter().map(|node| node.name()).collect();
+    assert_eq!(
+        all,
+        vec!["80s Mashup", "House", "Deep House", "French House"]
+    );
+}
+
+#[test]
+fn test_library_track_fields() {
+    let library = Library::read_from_path(LIBRARY_PATH).unwrap();
+    let track = library
+        .track(&PathBuf::from(
+            "ALAN BRAXE - INTRO ( Max Padovani Remix).mp3",
+        ))
+        .unwrap();
+    assert_eq!(
+        track.title.as_deref(),
+        Some("ALAN BRAXE - INTRO ( Max Padovani Remix)")
+    );
+    assert_eq!(track.file_type.as_deref(), Some("mp3"));
+    assert_eq!(track.key.as_deref(), Some("Fm"));
+    assert_eq!(track.bpm.as_deref(), Some("124.00"));
+    assert_eq!(track.length.as_deref(), Some("05:02.00"));
+    assert_eq!(track.bitrate.as_deref(), Some("320.0kbps"));
+    assert_eq!(track.sample_rate.as_deref(), Some("44.1k"));
+    assert_eq!(track.file_size.as_deref(), Some("11.5MB"));
+    assert_eq!(track.file_time, Some(1580988450));
+    assert_eq!(track.date_added, Some(1580992490));
+    assert_eq!(track.date_added_str.as_deref(), Some("1580992490"));
+    assert_eq!(track.year, None);
+
+    let genres: Vec<&str> = library
+        .tracks()
+        .filter_map(|track| track.genre.as_deref())
+        .collect();
+    assert!(genres.contains(&"Funky Tech"));
+}
+
+#[test]
+fn test_library_track_typed_values() {
+    let library = Library::read_from_path(LIBRARY_PATH).unwrap();
+    let mut track = library
+        .track(&PathBuf::from("CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3"))
+        .unwrap()
+        .clone();
+    assert_eq!(track.bpm_value(), Some(126.0));
+    assert_eq!(track.bitrate_kbps(), Some(320.0));
+    assert_eq!(track.sample_rate_hz(), Some(44100));
+    assert_eq!(track.file_size_bytes(), Some(15309210));
+    assert_eq!(track.duration(), Some(Duration::from_millis(382930)));
+    assert_eq!(
+        track.date_added_time(),
+        Some(UNIX_EPOCH + Duration::from_secs(1580992490))
+    );
+    assert_eq!(
+        track.file_time_modified(),
+        Some(UNIX_EPOCH + Duration::from_secs(1580989282))
+    );
+
+    track.set_bpm_value(128.0);
+    track.set_bitrate_kbps(256.0);
+    track.set_sample_rate_hz(48000);
+    track.set_file_size_bytes(15319300);
+    track.set_duration(Duration::from_millis(225120));
+    track.set_date_added_time(UNIX_EPOCH + Duration::from_secs(1600000000));
+    assert_eq!(track.bpm.as_deref(), Some("128.00"));
+    assert_eq!(track.bitrate.as_deref(), Some("256.0kbps"));
+    assert_eq!(track.sample_rate.as_deref(), Some("48.0k"));
+    assert_eq!(track.file_size.as_deref(), Some("14.6MB"));
+    assert_eq!(track.length.as_deref(), Some("03:45.12"));
+    assert_eq!(track.date_added, Some(1600000000));
+    assert_eq!(track.date_added_str.as_deref(), Some("1600000000"));
+}